@@ -0,0 +1,55 @@
+use super::Cpi;
+
+use solana_program::{
+    account_info::AccountInfo,
+    entrypoint::ProgramResult, program::invoke,
+};
+use web3_domain_name_service::instruction::NameRegistryInstruction;
+
+impl Cpi {
+
+    /// The parent owner signs the outer transaction, so no registrar seeds are needed
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_subdomain_account<'a>(
+        name_service_program: &AccountInfo<'a>,
+        system_program_account: &AccountInfo<'a>,
+        name_account: &AccountInfo<'a>,
+        fee_payer: &AccountInfo<'a>,
+        owner: &AccountInfo<'a>,
+        parent_name_account: &AccountInfo<'a>,
+        parent_name_owner: &AccountInfo<'a>,
+        hashed_name: Vec<u8>,
+        lamports: u64,
+    ) -> ProgramResult {
+        let create_name_instruction = web3_domain_name_service::instruction::create(
+            *name_service_program.key,
+            NameRegistryInstruction::Create {
+                hashed_name,
+                lamports,
+                space: 0,
+                custom_value: None,
+            },
+            *name_account.key,
+            *fee_payer.key,
+            *owner.key,
+            None,
+            None,
+            Some(*parent_name_account.key),
+            Some(*parent_name_owner.key),
+        )?;
+
+        // no class and no previewer: both resolve to the default key, which is the system program
+        invoke(
+            &create_name_instruction,
+            &[
+                name_service_program.clone(),
+                system_program_account.clone(),
+                fee_payer.clone(),
+                name_account.clone(),
+                owner.clone(),
+                parent_name_account.clone(),
+                parent_name_owner.clone(),
+            ],
+        )
+    }
+}
@@ -5,6 +5,7 @@ pub mod create_root_name_account;
 pub mod create_reverse_account;
 pub mod transfer_name_account;
 pub mod chage_preview;
+pub mod create_subdomain_account;
 
 pub struct Cpi {}
 
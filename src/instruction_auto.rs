@@ -32,5 +32,68 @@ pub enum ProgramInstruction {
 
     /// Init usr record account
     InitUsr,
+
+    /// Create or update the registrar config
+    SetConfig,
+
+    /// Extend the term of a settled domain
+    Renew,
+
+    /// Set the fee for subdomains under a domain
+    SetSubdomainFee,
+
+    /// Issue a subdomain under a settled domain
+    CreateSubdomain,
+
+    /// Begin several name auctions at once
+    BatchBeginNameRegistration,
+
+    /// Settle several expired auctions at once
+    BatchFinalizeName,
+
+    /// Accept an SPL token for bids under a root domain
+    SetAcceptedMint,
+
+    /// Begin a name auction paid in an accepted SPL token
+    BeginNameRegistrationWithToken,
+
+    /// Increase the bid of an auction paid in an SPL token
+    IncreaseBidWithToken,
+
+    /// Withdraw profit earned in an SPL token
+    WithdrawToken,
+
+    /// List the name states that can be settled now, read-only
+    ListSettleable,
+
+    /// Move to another referrer after the cooldown
+    ChangeReferrer,
+
+    /// Name a delegate that can withdraw a record's profit
+    SetWithdrawAuthority,
+
+    /// Withdraw all profit and close the referrer record
+    CloseAccount,
+
+    /// Create a leaderboard or change how many entries it keeps
+    SetLeaderboardSize,
+
+    /// Create the protocol statistics account
+    InitStats,
+
+    /// Pick one of the wallet's domains as its primary name
+    SetPrimaryName,
+
+    /// Set or clear the records a domain resolves to
+    UpdateRecords,
+
+    /// Hand a domain to the registrar for a supply-1 token
+    Wrap,
+
+    /// Burn a domain's token and take the domain back
+    Unwrap,
+
+    /// Withdraw protocol revenue earned in an SPL token
+    WithdrawAdminToken,
 }
 
@@ -31,6 +31,14 @@ pub mod constants {
 
         (vault_pda, bump)
     }
+
+    pub fn return_config_key() -> (Pubkey, u8) {
+        static CONFIG_SEED: &[u8] = b"config";
+
+        let (config_pda, bump) = Pubkey::find_program_address(&[CONFIG_SEED], &crate::ID);
+
+        (config_pda, bump)
+    }
 }
 
 #[cfg(not(feature = "devnet"))]
@@ -49,6 +57,14 @@ pub mod constants {
 
         (vault_pda, bump)
     }
+
+    pub fn return_config_key() -> (Pubkey, u8) {
+        static CONFIG_SEED: &[u8] = b"config";
+
+        let (config_pda, bump) = Pubkey::find_program_address(&[CONFIG_SEED], &crate::ID);
+
+        (config_pda, bump)
+    }
 }
 
 #[cfg(test)]
@@ -17,6 +17,27 @@ pub mod start_project;
 pub mod extract_admin;
 pub mod extract;
 pub mod init_usr;
+pub mod set_config;
+pub mod renew;
+pub mod set_subdomain_fee;
+pub mod create_subdomain;
+pub mod batch_start_name;
+pub mod batch_finalize_name;
+pub mod set_accepted_mint;
+pub mod start_name_token;
+pub mod increase_price_token;
+pub mod extract_token;
+pub mod list_settleable;
+pub mod change_referrer;
+pub mod set_withdraw_authority;
+pub mod close_account;
+pub mod set_leaderboard_size;
+pub mod init_stats;
+pub mod set_primary_name;
+pub mod update_records;
+pub mod wrap;
+pub mod unwrap;
+pub mod extract_admin_token;
 
 pub struct Processor {}
 
@@ -90,6 +111,126 @@ impl Processor {
                     .map_err(|_| ProgramError::InvalidArgument)?;
                 init_usr::init_usr(program_id, accounts, params)?;
             }
+            ProgramInstruction::SetConfig => {
+                msg!("Instruction: set config");
+                let params = set_config::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                set_config::process_set_config(program_id, accounts, params)?;
+            }
+            ProgramInstruction::Renew => {
+                msg!("Instruction: renew domain");
+                let params = renew::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                renew::process_renew(program_id, accounts, params)?;
+            }
+            ProgramInstruction::SetSubdomainFee => {
+                msg!("Instruction: set subdomain fee");
+                let params = set_subdomain_fee::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                set_subdomain_fee::process_set_subdomain_fee(program_id, accounts, params)?;
+            }
+            ProgramInstruction::CreateSubdomain => {
+                msg!("Instruction: create subdomain");
+                let params = create_subdomain::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                create_subdomain::process_create_subdomain(program_id, accounts, params)?;
+            }
+            ProgramInstruction::BatchBeginNameRegistration => {
+                msg!("Instruction: batch create name domains");
+                let params = batch_start_name::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                batch_start_name::process_batch_start_name(program_id, accounts, params)?;
+            }
+            ProgramInstruction::BatchFinalizeName => {
+                msg!("Instruction: batch settle domain names");
+                let params = batch_finalize_name::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                batch_finalize_name::process_batch_finalize_name(program_id, accounts, params)?;
+            }
+            ProgramInstruction::SetAcceptedMint => {
+                msg!("Instruction: set accepted mint");
+                let params = set_accepted_mint::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                set_accepted_mint::process_set_accepted_mint(program_id, accounts, params)?;
+            }
+            ProgramInstruction::BeginNameRegistrationWithToken => {
+                msg!("Instruction: create name domain paid in token");
+                let params = start_name_token::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                start_name_token::process_start_name_token(program_id, accounts, params)?;
+            }
+            ProgramInstruction::IncreaseBidWithToken => {
+                msg!("Instruction: increase price in token");
+                let params = increase_price_token::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                increase_price_token::process_increase_price_token(program_id, accounts, params)?;
+            }
+            ProgramInstruction::WithdrawToken => {
+                msg!("Instruction: withdraw token profit");
+                let params = extract_token::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                extract_token::process_extract_token(program_id, accounts, params)?;
+            }
+            ProgramInstruction::ListSettleable => {
+                msg!("Instruction: list settleable name states");
+                list_settleable::process_list_settleable(program_id, accounts)?;
+            }
+            ProgramInstruction::ChangeReferrer => {
+                msg!("Instruction: change referrer");
+                let params = change_referrer::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                change_referrer::process_change_referrer(program_id, accounts, params)?;
+            }
+            ProgramInstruction::SetWithdrawAuthority => {
+                msg!("Instruction: set withdraw authority");
+                let params = set_withdraw_authority::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                set_withdraw_authority::process_set_withdraw_authority(program_id, accounts, params)?;
+            }
+            ProgramInstruction::CloseAccount => {
+                msg!("Instruction: close account");
+                close_account::process_close_account(program_id, accounts)?;
+            }
+            ProgramInstruction::SetLeaderboardSize => {
+                msg!("Instruction: set leaderboard size");
+                let params = set_leaderboard_size::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                set_leaderboard_size::process_set_leaderboard_size(program_id, accounts, params)?;
+            }
+            ProgramInstruction::InitStats => {
+                msg!("Instruction: init stats");
+                init_stats::process_init_stats(program_id, accounts)?;
+            }
+            ProgramInstruction::SetPrimaryName => {
+                msg!("Instruction: set primary name");
+                let params = set_primary_name::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                set_primary_name::process_set_primary_name(program_id, accounts, params)?;
+            }
+            ProgramInstruction::UpdateRecords => {
+                msg!("Instruction: update records");
+                let params = update_records::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                update_records::process_update_records(program_id, accounts, params)?;
+            }
+            ProgramInstruction::Wrap => {
+                msg!("Instruction: wrap");
+                let params = wrap::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                wrap::process_wrap(program_id, accounts, params)?;
+            }
+            ProgramInstruction::Unwrap => {
+                msg!("Instruction: unwrap");
+                let params = unwrap::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                unwrap::process_unwrap(program_id, accounts, params)?;
+            }
+            ProgramInstruction::WithdrawAdminToken => {
+                msg!("Instruction: admin extract token");
+                let params = extract_admin_token::Params::try_from_slice(instruction_data)
+                    .map_err(|_| ProgramError::InvalidArgument)?;
+                extract_admin_token::process_extract_admin_token(program_id, accounts, params)?;
+            }
         }
 
         Ok(())
@@ -0,0 +1,75 @@
+//! Settle several expired auctions in one instruction
+
+use web3_utils::borsh_size::BorshSize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{processor::finalize_name, utils::batch::{expand_item_accounts, MAX_BATCH_FINALIZE, next_account_group, run_batch_item, write_batch_result}};
+
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct Item {
+    pub params: finalize_name::Params,
+    /// number of accounts in this item's group: its own accounts in `FinalizeName` order,
+    /// those at `finalize_name::Accounts::SHARED` left out, then its trailing accounts
+    pub accounts_len: u8,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct Params {
+    pub items: Vec<Item>,
+}
+
+impl BorshSize for Item {
+    fn borsh_len(&self) -> usize {
+        self.params.borsh_len() + 1
+    }
+}
+
+// names differ in length, so the size of every item has to be summed
+impl BorshSize for Params {
+    fn borsh_len(&self) -> usize {
+        4 + self.items.iter().map(|item| item.borsh_len()).sum::<usize>()
+    }
+}
+
+/// accounts: the shared accounts once, in `FinalizeName` order, then one group per item, back to back.
+/// return data: a borsh `Vec<BatchItemStatus>` in item order
+pub fn process_batch_finalize_name(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    if params.items.is_empty() || params.items.len() > MAX_BATCH_FINALIZE {
+        msg!("batch takes 1 to {} names", MAX_BATCH_FINALIZE);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let shared_len = finalize_name::Accounts::SHARED.len();
+    let shared = accounts.get(..shared_len).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let mut offset = shared_len;
+    let mut statuses = Vec::with_capacity(params.items.len());
+
+    for (index, item) in params.items.into_iter().enumerate() {
+        let group = next_account_group(accounts, &mut offset, item.accounts_len)?;
+        let laid_out = expand_item_accounts(shared, &finalize_name::Accounts::SHARED, group);
+        let item_accounts = finalize_name::Accounts::parse(&laid_out, item.params.referrer_accounts)?;
+
+        // an auction someone else already settled fails the owner check and is skipped
+        let prepared = item_accounts.check()
+            .and_then(|_| finalize_name::prepare_finalize_name(&item_accounts, &item.params));
+
+        statuses.push(run_batch_item(index, prepared, |settlement| {
+            finalize_name::settle_name(&item_accounts, item.params, settlement)
+        })?);
+    }
+
+    if offset != accounts.len() {
+        msg!("{} accounts left over", accounts.len() - offset);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    write_batch_result(&statuses)
+}
@@ -0,0 +1,95 @@
+//! Open several auctions in one instruction
+
+use web3_utils::borsh_size::BorshSize;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{processor::start_name, utils::batch::{expand_item_accounts, MAX_BATCH_START, next_account_group, run_batch_item, write_batch_result}};
+
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct Item {
+    pub params: start_name::Params,
+    /// number of accounts in this item's group: its own accounts in `BeginNameRegistration` order,
+    /// those at `start_name::Accounts::SHARED` left out, then its trailing accounts
+    pub accounts_len: u8,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct Params {
+    pub items: Vec<Item>,
+}
+
+impl BorshSize for Item {
+    fn borsh_len(&self) -> usize {
+        self.params.borsh_len() + 1
+    }
+}
+
+// names differ in length, so the size of every item has to be summed
+impl BorshSize for Params {
+    fn borsh_len(&self) -> usize {
+        4 + self.items.iter().map(|item| item.borsh_len()).sum::<usize>()
+    }
+}
+
+/// accounts: the shared accounts once, in `BeginNameRegistration` order, then one group per item, back to back.
+/// return data: a borsh `Vec<BatchItemStatus>` in item order
+pub fn process_batch_start_name(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    if params.items.is_empty() || params.items.len() > MAX_BATCH_START {
+        msg!("batch takes 1 to {} names", MAX_BATCH_START);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let shared_len = start_name::Accounts::SHARED.len();
+    let shared = accounts.get(..shared_len).ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let mut offset = shared_len;
+    let mut statuses = Vec::with_capacity(params.items.len());
+
+    for (index, item) in params.items.into_iter().enumerate() {
+        let group = next_account_group(accounts, &mut offset, item.accounts_len)?;
+        let laid_out = expand_item_accounts(shared, &start_name::Accounts::SHARED, group);
+        let item_accounts = start_name::Accounts::parse(&laid_out)?;
+
+        let prepared = item_accounts.check()
+            .and_then(|_| start_name::prepare_start_name(&item_accounts, &item.params, None));
+
+        statuses.push(run_batch_item(index, prepared, |opening| {
+            start_name::open_auction(program_id, &item_accounts, item.params, opening, None)
+        })?);
+    }
+
+    if offset != accounts.len() {
+        msg!("{} accounts left over", accounts.len() - offset);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    write_batch_result(&statuses)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn borsh_len_matches_serialized_len() {
+        let item = |name: &str| Item {
+            params: start_name::Params {
+                name: name.to_string(),
+                root_name: "kilo".to_string(),
+                price_sol: 10_000_000,
+                referrer_key: Pubkey::default(),
+            },
+            accounts_len: (start_name::Accounts::FIXED_LEN - start_name::Accounts::SHARED.len()) as u8,
+        };
+        let params = Params { items: vec![item("a"), item("a-much-longer-name")] };
+
+        assert_eq!(params.borsh_len(), params.try_to_vec().unwrap().len());
+    }
+}
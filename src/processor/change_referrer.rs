@@ -0,0 +1,234 @@
+//! Let a usr move to another referrer after a cooldown
+
+use web3_utils::{
+    accounts::InstructionsAccount,
+    borsh_size::BorshSize,
+    check::{check_account_key, check_account_owner, check_signer},
+    BorshSize,
+    InstructionsAccount
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    constants::{return_config_key, return_vault_key},
+    state::{ReferrerRecordHeader, config::ConfigRecord, get_referrer_record_key},
+    utils::{event::{Event, emit}, get_now_time, if_referrer_valid, resize_pda_account}
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// the new referrer's wallet, or the vault to have none
+    pub new_referrer: Pubkey,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// the usr changing its referrer
+    #[cons(writable, signer)]
+    pub usr: &'a T,
+    /// usr's referrer record
+    #[cons(writable)]
+    pub usr_record: &'a T,
+    /// the registrar config
+    pub config: &'a T,
+    /// vault
+    pub vault: &'a T,
+    /// the current referrer's record -- the vault when there is none
+    #[cons(writable)]
+    pub old_referrer_record: &'a T,
+    /// the new referrer's record -- the vault when there is none
+    #[cons(writable)]
+    pub new_referrer_record: &'a T,
+    /// the records above the new referrer, nearest first, up to the vault
+    pub new_referrer_ancestors: &'a [T],
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            usr: next_account_info(accounts_iter)?,
+            usr_record: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+            vault: next_account_info(accounts_iter)?,
+            old_referrer_record: next_account_info(accounts_iter)?,
+            new_referrer_record: next_account_info(accounts_iter)?,
+            new_referrer_ancestors: accounts_iter.as_slice(),
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+        check_account_key(self.config, &return_config_key().0)?;
+        msg!("config id ok");
+        check_account_key(self.vault, &return_vault_key().0)?;
+        msg!("vault ok");
+
+        check_account_key(self.usr_record, &get_referrer_record_key(self.usr.key).0)?;
+        check_account_owner(self.usr_record, &crate::ID)?;
+        msg!("usr record ok");
+
+        check_signer(self.usr)?;
+        msg!("usr signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_change_referrer<'a, 'b: 'a>(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let vault_key = *accounts.vault.key;
+    let now = get_now_time()?;
+    let config = ConfigRecord::load(accounts.config)?;
+
+    let usr_record = ReferrerRecordHeader::unpack_from_slice(&accounts.usr_record.data.borrow())?;
+    let old_referrer = usr_record.referrer_account;
+
+    if params.new_referrer == old_referrer {
+        msg!("already the referrer");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if &params.new_referrer == accounts.usr.key {
+        msg!("can't refer yourself");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let last_change = if usr_record.referrer_changed_at != 0 {
+        usr_record.referrer_changed_at
+    } else {
+        usr_record.create_time
+    };
+    let next_change = last_change
+        .checked_add(config.referrer_change_cooldown)
+        .ok_or(ProgramError::InvalidArgument)?;
+    if now < next_change {
+        msg!("referrer can be changed again at {}", next_change);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if params.new_referrer != vault_key {
+        check_account_key(accounts.new_referrer_record, &get_referrer_record_key(&params.new_referrer).0)?;
+        check_account_owner(accounts.new_referrer_record, &crate::ID)?;
+        let new_referrer_record =
+            ReferrerRecordHeader::unpack_from_slice(&accounts.new_referrer_record.data.borrow())?;
+
+        if !if_referrer_valid(&new_referrer_record, config.referrer_maturity())? {
+            msg!("new referrer isn't mature yet");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        check_not_descendant(&accounts, &new_referrer_record, &vault_key)?;
+    } else {
+        check_account_key(accounts.new_referrer_record, &vault_key)?;
+    }
+    msg!("new referrer ok");
+
+    if old_referrer != vault_key {
+        check_account_key(accounts.old_referrer_record, &get_referrer_record_key(&old_referrer).0)?;
+        // nothing to update once the old referrer has left
+        if !accounts.old_referrer_record.data_is_empty() {
+            update_direct_invitees(&accounts, accounts.old_referrer_record, false)?;
+        }
+    } else {
+        check_account_key(accounts.old_referrer_record, &vault_key)?;
+    }
+    if params.new_referrer != vault_key {
+        update_direct_invitees(&accounts, accounts.new_referrer_record, true)?;
+    }
+
+    resize_pda_account(
+        accounts.usr,
+        accounts.usr_record,
+        accounts.system_program,
+        ReferrerRecordHeader::LEN,
+    )?;
+    let mut data_ref = accounts.usr_record.try_borrow_mut_data()?;
+    let mut usr_record = ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
+    usr_record.referrer_account = params.new_referrer;
+    usr_record.referrer_changed_at = now;
+    usr_record.try_pack_into_slice(&mut data_ref)?;
+    msg!("referrer changed: {} -> {}", old_referrer, params.new_referrer);
+
+    emit(&Event::ReferrerChanged {
+        usr: *accounts.usr.key,
+        old_referrer,
+        new_referrer: params.new_referrer,
+        time: now,
+    })
+}
+
+/// Walk up from the new referrer to the vault, the usr mustn't show up on the way
+fn check_not_descendant(
+    accounts: &Accounts<'_, AccountInfo<'_>>,
+    new_referrer_record: &ReferrerRecordHeader,
+    vault_key: &Pubkey,
+) -> ProgramResult {
+
+    let mut ancestor = new_referrer_record.referrer_account;
+    let mut records = accounts.new_referrer_ancestors.iter();
+
+    while &ancestor != vault_key {
+        if &ancestor == accounts.usr.key {
+            msg!("new referrer is in your downline");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let record = records.next().ok_or_else(|| {
+            msg!("should provide the record of {}", ancestor);
+            ProgramError::NotEnoughAccountKeys
+        })?;
+        check_account_key(record, &get_referrer_record_key(&ancestor).0)?;
+        if record.data_is_empty() {
+            // an ancestor that left ends the chain like the vault does
+            break;
+        }
+
+        ancestor = ReferrerRecordHeader::unpack_from_slice(&record.data.borrow())?.referrer_account;
+    }
+
+    Ok(())
+}
+
+fn update_direct_invitees<'b>(
+    accounts: &Accounts<'_, AccountInfo<'b>>,
+    record: &AccountInfo<'b>,
+    joined: bool,
+) -> ProgramResult {
+
+    resize_pda_account(accounts.usr, record, accounts.system_program, ReferrerRecordHeader::LEN)?;
+
+    let mut data_ref = record.try_borrow_mut_data()?;
+    let mut record_data = ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
+    let invitees = &mut record_data.downline.direct_invitees;
+    *invitees = if joined {
+        invitees.checked_add(1).ok_or(ProgramError::InvalidArgument)?
+    } else {
+        invitees.saturating_sub(1)
+    };
+    record_data.try_pack_into_slice(&mut data_ref)?;
+
+    Ok(())
+}
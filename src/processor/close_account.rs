@@ -0,0 +1,186 @@
+//! Withdraw all profit and close the usr's referrer record and its ledger
+
+use web3_utils::{
+    accounts::InstructionsAccount,
+    check::{check_account_key, check_account_owner, check_signer},
+    InstructionsAccount
+};
+
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    constants::{return_config_key, return_vault_key},
+    state::{ReferrerRecordHeader, config::ConfigRecord, get_referrer_record_key, portfolio::{PortfolioRecord, get_portfolio_key}},
+    utils::{get_now_time, ledger::close_ledger, math, resize_pda_account, share_with_cap, stats::record_withdrawal, vault::{pay_from_vault, update_vault_record}}
+};
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// the record's owner
+    #[cons(writable, signer)]
+    pub user: &'a T,
+    /// user's referrer record, closed
+    #[cons(writable)]
+    pub user_referrer_record: &'a T,
+    /// the record's profit ledger, closed along with it
+    #[cons(writable)]
+    pub user_ledger: &'a T,
+    /// the referrer's record, one invitee fewer -- the vault when there is none
+    #[cons(writable)]
+    pub referrer_record: &'a T,
+    /// vault
+    #[cons(writable)]
+    pub vault: &'a T,
+    /// the protocol statistics -- left alone until the admins create them
+    #[cons(writable)]
+    pub stats: &'a T,
+    /// the registrar config
+    pub config: &'a T,
+    /// the user's portfolio -- it can't list a domain or a bid
+    pub portfolio: &'a T,
+    /// where the profit and the rent of the record and its ledger go -- the user when left out
+    #[cons(writable)]
+    pub destination: Option<&'a T>,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            user: next_account_info(accounts_iter)?,
+            user_referrer_record: next_account_info(accounts_iter)?,
+            user_ledger: next_account_info(accounts_iter)?,
+            referrer_record: next_account_info(accounts_iter)?,
+            vault: next_account_info(accounts_iter)?,
+            stats: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+            portfolio: next_account_info(accounts_iter)?,
+            destination: next_account_info(accounts_iter).ok(),
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+        check_account_key(self.vault, &return_vault_key().0)?;
+        msg!("vault ok");
+        check_account_key(self.config, &return_config_key().0)?;
+        msg!("config ok");
+
+        check_account_key(self.user_referrer_record, &get_referrer_record_key(self.user.key).0)?;
+        check_account_owner(self.user_referrer_record, &crate::ID)?;
+        msg!("user record ok");
+
+        check_signer(self.user)?;
+        msg!("user signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_close_account(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let record_data =
+        ReferrerRecordHeader::unpack_from_slice(&accounts.user_referrer_record.data.borrow())?;
+
+    if record_data.token_profits.iter().any(|slot| slot.profit > 0) {
+        msg!("withdraw the token profit first");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // settling a domain sold by the user, or an auction it leads, credits its record
+    check_account_key(accounts.portfolio, &get_portfolio_key(accounts.user.key).0)?;
+    if !accounts.portfolio.data_is_empty() {
+        check_account_owner(accounts.portfolio, &crate::ID)?;
+        let portfolio = PortfolioRecord::unpack(&accounts.portfolio.data.borrow())?;
+        if !portfolio.domains.is_empty() || !portfolio.bids.is_empty() {
+            msg!("user still holds {} domains and {} bids", portfolio.domains.len(), portfolio.bids.len());
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+
+    // signing up again picks a new referrer, so closing waits out the referrer change cooldown
+    let config = ConfigRecord::load(accounts.config)?;
+    let last_change = if record_data.referrer_changed_at != 0 {
+        record_data.referrer_changed_at
+    } else {
+        record_data.create_time
+    };
+    let closable_at = last_change
+        .checked_add(config.referrer_change_cooldown)
+        .ok_or(ProgramError::InvalidArgument)?;
+    if get_now_time()? < closable_at {
+        msg!("record can be closed at {}", closable_at);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let destination = accounts.destination.unwrap_or(accounts.user);
+
+    // the whole balance goes, less the usual 1% fee
+    let real_ex = share_with_cap(record_data.profit, 990_000_000)?;
+    if record_data.profit > 0 {
+        pay_from_vault(accounts.vault, destination, real_ex)?;
+        msg!("withdraw {} to {}", real_ex, destination.key);
+        record_withdrawal(accounts.stats, record_data.profit, math::sub(record_data.profit, real_ex)?)?;
+    }
+    update_vault_record(accounts.user, accounts.vault, accounts.system_program, |vault| {
+        vault.usr_count = vault.usr_count.saturating_sub(1);
+        vault.settle_owed(record_data.profit);
+        vault.earn(math::sub(record_data.profit, real_ex)?)
+    })?;
+
+    let referrer = record_data.referrer_account;
+    if &referrer != accounts.vault.key {
+        check_account_key(accounts.referrer_record, &get_referrer_record_key(&referrer).0)?;
+        if !accounts.referrer_record.data_is_empty() {
+            resize_pda_account(
+                accounts.user,
+                accounts.referrer_record,
+                accounts.system_program,
+                ReferrerRecordHeader::LEN,
+            )?;
+            let mut data_ref = accounts.referrer_record.try_borrow_mut_data()?;
+            let mut referrer_data = ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
+            referrer_data.downline.direct_invitees = referrer_data.downline.direct_invitees.saturating_sub(1);
+            referrer_data.pack_into_slice(&mut data_ref);
+        }
+    } else {
+        check_account_key(accounts.referrer_record, accounts.vault.key)?;
+    }
+
+    {
+        let lamports = **accounts.user_referrer_record.lamports.borrow();
+        **accounts.user_referrer_record.try_borrow_mut_lamports()? -= lamports;
+        **destination.try_borrow_mut_lamports()? += lamports;
+        msg!("return record rent: {} to {}", lamports, destination.key);
+
+        let mut data = accounts.user_referrer_record.try_borrow_mut_data()?;
+        for byte in data.iter_mut() {
+            *byte = 0;
+        }
+        accounts.user_referrer_record.assign(&solana_program::system_program::ID);
+    }
+    close_ledger(accounts.user_referrer_record, accounts.user_ledger, destination)?;
+    msg!("record of {} closed", accounts.user.key);
+
+    Ok(())
+}
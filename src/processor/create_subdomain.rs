@@ -0,0 +1,290 @@
+//! Issue a `sub.name.root` record under a settled domain
+
+use web3_utils::{
+    accounts::InstructionsAccount, 
+    borsh_size::BorshSize, 
+    check::{check_account_key, check_account_owner, check_signer}, 
+    BorshSize, 
+    InstructionsAccount
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, 
+    entrypoint::ProgramResult, 
+    msg, 
+    program::invoke, 
+    program_error::ProgramError, 
+    program_pack::Pack, 
+    pubkey::Pubkey, 
+    rent::Rent, 
+    sysvar::Sysvar, 
+};
+use solana_system_interface::instruction as system_instruction;
+use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
+
+use crate::{
+    central_state, 
+    constants::{return_config_key, return_vault_key}, 
+    cpi::Cpi, 
+    state::{RegistrationRecordHeader, config::ConfigRecord, get_referrer_record_key, get_registration_key, ledger::LedgerKind, subdomain::{SubdomainFeeRecord, get_subdomain_fee_key}}, 
+    utils::{get_hashed_name, get_now_time, ledger::ledger_entry, stats::record_fee, transfer_by_chain::add_profit, vault::update_vault_record}
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// the parent domain, without the root
+    pub parent_name: String,
+    /// the new subdomain, without the parent
+    pub sub_name: String,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The naming service program ID
+    pub naming_service_program: &'a T,
+    /// The system program account
+    pub system_program: &'a T,
+    /// The central state account
+    pub central_state: &'a T,
+    /// rent sysvar
+    pub rent_sysvar: &'a T,
+    /// The root domain account
+    pub root_domain: &'a T,
+    /// The parent domain name account
+    pub parent_domain: &'a T,
+    /// The parent domain owner
+    #[cons(signer)]
+    pub parent_owner: &'a T,
+    /// parent owner's referrer record -- the fee is credited here
+    #[cons(writable)]
+    pub parent_owner_record: &'a T,
+    /// parent owner's profit ledger
+    #[cons(writable)]
+    pub parent_owner_ledger: &'a T,
+    /// the parent domain's registration record
+    pub parent_registration: &'a T,
+    /// the registrar config
+    pub config: &'a T,
+    /// the parent domain's subdomain fee record -- may be empty
+    pub subdomain_fee_record: &'a T,
+    /// The subdomain name account
+    #[cons(writable)]
+    pub subdomain_account: &'a T,
+    /// The subdomain reverse look up account
+    #[cons(writable)]
+    pub subdomain_reverse_lookup: &'a T,
+    /// The subdomain owner
+    pub subdomain_owner: &'a T,
+    /// pays the fee and the rent
+    #[cons(writable, signer)]
+    pub fee_payer: &'a T,
+    /// vault
+    #[cons(writable)]
+    pub vault: &'a T,
+    /// the protocol statistics -- left alone until the admins create them
+    #[cons(writable)]
+    pub stats: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            naming_service_program: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+            central_state: next_account_info(accounts_iter)?,
+            rent_sysvar: next_account_info(accounts_iter)?,
+            root_domain: next_account_info(accounts_iter)?,
+            parent_domain: next_account_info(accounts_iter)?,
+            parent_owner: next_account_info(accounts_iter)?,
+            parent_owner_record: next_account_info(accounts_iter)?,
+            parent_owner_ledger: next_account_info(accounts_iter)?,
+            parent_registration: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+            subdomain_fee_record: next_account_info(accounts_iter)?,
+            subdomain_account: next_account_info(accounts_iter)?,
+            subdomain_reverse_lookup: next_account_info(accounts_iter)?,
+            subdomain_owner: next_account_info(accounts_iter)?,
+            fee_payer: next_account_info(accounts_iter)?,
+            vault: next_account_info(accounts_iter)?,
+            stats: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.naming_service_program, &web3_domain_name_service::ID)?;
+        msg!("nameservice id ok");
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+        check_account_key(self.central_state, &central_state::KEY)?;
+        msg!("central_state id ok");
+        check_account_key(self.config, &return_config_key().0)?;
+        msg!("config id ok");
+        check_account_key(self.vault, &return_vault_key().0)?;
+        msg!("vault ok");
+
+        check_account_owner(self.root_domain, &web3_domain_name_service::ID)?;
+        check_account_owner(self.parent_domain, &web3_domain_name_service::ID)?;
+
+        check_signer(self.parent_owner)?;
+        msg!("parent owner signature ok");
+        check_signer(self.fee_payer)?;
+        msg!("fee_payer signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_create_subdomain<'a, 'b: 'a>(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    if params.sub_name.is_empty() || params.sub_name != params.sub_name.trim().to_lowercase() {
+        msg!("Domain names must be lower case and have no space");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if params.sub_name.contains('.') {
+        msg!("domain contains invalid puncation");
+        return Err(ProgramError::InvalidArgument);
+    }
+    msg!("subdomain: {}.{}", params.sub_name, params.parent_name);
+
+    let root_record = NameRecordHeader::unpack_from_slice(&accounts.root_domain.data.borrow())?;
+    if root_record.owner != central_state::KEY {
+        msg!("root is not managed by the registrar");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (parent_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID, 
+        get_hashed_name(&params.parent_name), 
+        None, 
+        Some(accounts.root_domain.key)
+    );
+    check_account_key(accounts.parent_domain, &parent_key)?;
+    msg!("parent domain key ok");
+
+    let parent_record = NameRecordHeader::unpack_from_slice(&accounts.parent_domain.data.borrow())?;
+    if parent_record.owner == central_state::KEY {
+        msg!("parent domain is not settled yet");
+        return Err(ProgramError::InvalidArgument);
+    }
+    check_account_key(accounts.parent_owner, &parent_record.owner)?;
+    msg!("parent owner ok");
+
+    let (registration_key, _) = get_registration_key(&params.parent_name, accounts.root_domain.key);
+    check_account_key(accounts.parent_registration, &registration_key)?;
+    if !accounts.parent_registration.data_is_empty() {
+        let config = ConfigRecord::load(accounts.config)?;
+        let registration = 
+            RegistrationRecordHeader::unpack_from_slice(&accounts.parent_registration.data.borrow())?;
+        if registration.is_lapsed(config.grace_period, get_now_time()?) || registration.is_reclaimed() {
+            msg!("parent registration lapsed");
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+    msg!("parent registration ok");
+
+    let (fee_record_key, _) = get_subdomain_fee_key(accounts.parent_domain.key);
+    check_account_key(accounts.subdomain_fee_record, &fee_record_key)?;
+
+    let fee = if accounts.subdomain_fee_record.data_is_empty() || accounts.fee_payer.key == accounts.parent_owner.key {
+        0
+    } else {
+        SubdomainFeeRecord::unpack_from_slice(&accounts.subdomain_fee_record.data.borrow())?.fee
+    };
+
+    if fee > 0 {
+        let (parent_owner_record_key, _) = get_referrer_record_key(accounts.parent_owner.key);
+        check_account_key(accounts.parent_owner_record, &parent_owner_record_key)?;
+        check_account_owner(accounts.parent_owner_record, &crate::ID)?;
+
+        invoke(
+            &system_instruction::transfer(
+                accounts.fee_payer.key, accounts.vault.key, fee), 
+                &[
+                    accounts.fee_payer.clone(),
+                    accounts.vault.clone(),
+                    accounts.system_program.clone(),
+                ]
+        )?;
+        msg!("transfer subdomain fee to vault: {:?} lamports", fee);
+
+        add_profit(
+            accounts.parent_owner_record,
+            accounts.parent_owner_ledger,
+            &ledger_entry(LedgerKind::SubdomainFee, *accounts.subdomain_account.key, Pubkey::default(), fee)?,
+            accounts.fee_payer,
+            accounts.system_program,
+        )?;
+        msg!("add parent owner profit ok");
+
+        update_vault_record(accounts.fee_payer, accounts.vault, accounts.system_program, |vault| {
+            vault.owe(fee)
+        })?;
+        record_fee(accounts.stats, fee)?;
+    }
+
+    let hashed_name = get_hashed_name(&params.sub_name);
+    let (subdomain_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID, 
+        hashed_name.clone(), 
+        None, 
+        Some(accounts.parent_domain.key)
+    );
+    check_account_key(accounts.subdomain_account, &subdomain_key)?;
+    msg!("subdomain key ok");
+
+    let hashed_reverse_lookup = get_hashed_name(&subdomain_key.to_string());
+    let (reverse_lookup_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        hashed_reverse_lookup.clone(),
+        Some(&central_state::KEY),
+        None,
+    );
+    check_account_key(accounts.subdomain_reverse_lookup, &reverse_lookup_key)?;
+    msg!("reverse account key ok");
+
+    let rent = Rent::get()?;
+    Cpi::create_subdomain_account(
+        accounts.naming_service_program, 
+        accounts.system_program, 
+        accounts.subdomain_account, 
+        accounts.fee_payer, 
+        accounts.subdomain_owner, 
+        accounts.parent_domain, 
+        accounts.parent_owner, 
+        hashed_name, 
+        rent.minimum_balance(NameRecordHeader::LEN),
+    )?;
+    msg!("create subdomain ok");
+
+    if accounts.subdomain_reverse_lookup.data_len() == 0 {
+        let central_state_signer_seeds: &[&[u8]] = &[&crate::ID.to_bytes(), &[central_state::NONCE]];
+        Cpi::create_reverse_lookup_account(
+            accounts.naming_service_program, 
+            accounts.system_program, 
+            accounts.subdomain_reverse_lookup, 
+            accounts.fee_payer, 
+            params.sub_name, 
+            hashed_reverse_lookup, 
+            accounts.central_state, 
+            accounts.rent_sysvar, 
+            central_state_signer_seeds, 
+            None, 
+            None
+        )?;
+        msg!("create subdomain reverse ok");
+    }
+
+    Ok(())
+}
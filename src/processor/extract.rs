@@ -7,7 +7,7 @@ use solana_program::{
     account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
 };
 
-use crate::{constants::return_vault_key, state::{ReferrerRecordHeader, get_referrer_record_key}, utils::{math, share_with_cap}};
+use crate::{constants::return_vault_key, state::{ReferrerRecordHeader, get_referrer_record_key, ledger::LedgerKind}, utils::{ledger::{append_ledger, ledger_entry}, math, resize_pda_account, share_with_cap, stats::record_withdrawal, vault::{pay_from_vault, update_vault_record}}};
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
 /// The required parameters for the `create` instruction
@@ -19,12 +19,27 @@ pub struct Params {
 #[derive(InstructionsAccount)]
 /// The required accounts for the `create` instruction
 pub struct Accounts<'a, T> { 
-    #[cons(writable, signer)]
+    /// the record's owner, signs unless its withdraw authority does
+    #[cons(writable)]
     pub user: &'a T,
     #[cons(writable)]
     pub user_referrer_record: &'a T,
+    /// the record's profit ledger
+    #[cons(writable)]
+    pub user_ledger: &'a T,
     #[cons(writable)]
     pub vault: &'a T,
+    /// The system program account -- to grow a record written by an older version
+    pub system_program: &'a T,
+    /// the protocol statistics -- left alone until the admins create them
+    #[cons(writable)]
+    pub stats: &'a T,
+    /// the user's withdraw authority, when it signs instead of the user
+    #[cons(writable, signer)]
+    pub authority: Option<&'a T>,
+    /// where the lamports go -- the user when left out, and always when the authority signs
+    #[cons(writable)]
+    pub destination: Option<&'a T>,
 }
 
 impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
@@ -35,19 +50,30 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
         Ok(Accounts {
             user:next_account_info(accounts_iter)?,
             user_referrer_record: next_account_info(accounts_iter)?,
+            user_ledger: next_account_info(accounts_iter)?,
             vault: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+            stats: next_account_info(accounts_iter)?,
+            authority: next_account_info(accounts_iter).ok(),
+            destination: next_account_info(accounts_iter).ok(),
         })
     }
 
     pub fn check(&self) -> Result<(), ProgramError> {
 
         check_account_owner(self.user_referrer_record, &crate::ID)?;
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
 
-        check_signer(self.user).unwrap();
-        msg!("user signature ok");
+        check_signer(self.signer())?;
+        msg!("signature ok");
 
         Ok(())
     }
+
+    /// Who signs the withdrawal and pays any rent it needs
+    pub fn signer(&self) -> &'a AccountInfo<'b> {
+        self.authority.unwrap_or(self.user)
+    }
 }
 
 pub fn process_extract<'a, 'b: 'a>(
@@ -69,9 +95,23 @@ pub fn process_extract<'a, 'b: 'a>(
     check_account_key(accounts.vault, &vault_key)?;
     msg!("vault key ok");
 
-    let mut data_ref = accounts.user_referrer_record.try_borrow_mut_data()?;
+    let signer = accounts.signer();
+    let record_data = 
+        ReferrerRecordHeader::unpack_from_slice(&accounts.user_referrer_record.data.borrow())?;
+    if !record_data.can_withdraw(accounts.user.key, signer.key) {
+        msg!("{} can't withdraw for {}", signer.key, accounts.user.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    resize_pda_account(
+        signer,
+        accounts.user_referrer_record,
+        accounts.system_program,
+        ReferrerRecordHeader::LEN,
+    )?;
+
     let mut record_data = 
-        ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
+        ReferrerRecordHeader::unpack_from_slice(&accounts.user_referrer_record.data.borrow())?;
 
     // devnet - 0.01SOL Mainnet - 0.1SOL 
     if math::sub(record_data.profit, params.extraction)? <= 10_000_000 {
@@ -81,12 +121,31 @@ pub fn process_extract<'a, 'b: 'a>(
 
     let real_ex = share_with_cap(params.extraction, 990_000_000)?;
 
-    **accounts.vault.try_borrow_mut_lamports()? -= real_ex;
-    **accounts.user.try_borrow_mut_lamports()? += real_ex;
-    msg!("transfer ok");
+    let destination = accounts.destination.unwrap_or(accounts.user);
+    // a delegate can take the profit out, but only to its owner
+    if signer.key != accounts.user.key && destination.key != accounts.user.key {
+        msg!("withdraw authority can only pay {}", accounts.user.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+    pay_from_vault(accounts.vault, destination, real_ex)?;
+    msg!("transfer {} to {} ok", real_ex, destination.key);
 
     record_data.profit = math::sub(record_data.profit, params.extraction)?;
-    record_data.pack_into_slice(&mut data_ref); 
-
-    Ok(())
+    record_data.downline.withdrawn = math::add(record_data.downline.withdrawn, params.extraction)?;
+    record_data.try_pack_into_slice(&mut accounts.user_referrer_record.data.borrow_mut())?;
+
+    // the fee stays as revenue
+    update_vault_record(signer, accounts.vault, accounts.system_program, |vault| {
+        vault.settle_owed(params.extraction);
+        vault.earn(math::sub(params.extraction, real_ex)?)
+    })?;
+    record_withdrawal(accounts.stats, params.extraction, math::sub(params.extraction, real_ex)?)?;
+
+    append_ledger(
+        signer,
+        accounts.system_program,
+        accounts.user_referrer_record,
+        accounts.user_ledger,
+        &ledger_entry(LedgerKind::Withdrawal, Pubkey::default(), Pubkey::default(), params.extraction)?,
+    )
 }
\ No newline at end of file
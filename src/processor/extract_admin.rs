@@ -1,15 +1,23 @@
 use web3_utils::{
-    check::{check_signer},
+    check::{check_account_key, check_account_owner, check_signer},
     BorshSize, InstructionsAccount,
     borsh_size::BorshSize,
     accounts::InstructionsAccount,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
-    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, rent::Rent, sysvar::Sysvar
 };
 
-use crate::{constants::{ADMIN_ANDY, ADMIN_FANMOCHENG}, utils::share_with_cap};
+use crate::{
+    constants::{ADMIN_ANDY, ADMIN_FANMOCHENG, return_config_key, return_vault_key},
+    state::{
+        config::{ConfigRecord, TreasuryShare, TREASURY_BPS},
+        treasury::{TreasuryPayoutRecord, get_treasury_payout_key},
+        vault::VaultRecord,
+    },
+    utils::{create_pda_account, get_now_time, math, vault::{pay_from_vault, update_vault_record}},
+};
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
 /// The required parameters for the `create` instruction
@@ -21,11 +29,17 @@ pub struct Params {
 #[derive(InstructionsAccount)]
 /// The required accounts for the `create` instruction
 pub struct Accounts<'a, T> { 
+    /// The system program account
+    pub system_program: &'a T,
     #[cons(writable, signer)]
     pub admin_signer: &'a T,
-    pub admin_other: &'a T,
     #[cons(writable)]
     pub vault: &'a T,
+    /// the registrar config, holding the treasury table
+    pub config: &'a T,
+    /// (recipient, payout record) pairs in the treasury table's order
+    #[cons(writable)]
+    pub recipients: &'a [T],
 }
 
 impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
@@ -34,29 +48,36 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
     ) -> Result<Self, ProgramError> {
         let accounts_iter = &mut accounts.iter();
         Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
             admin_signer:next_account_info(accounts_iter)?,
-            admin_other: next_account_info(accounts_iter)?,
             vault: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+            recipients: accounts_iter.as_slice(),
         })
     }
 
     pub fn check(&self) -> Result<(), ProgramError> {
 
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+
         let admin_one = self.admin_signer.key;
         if admin_one != &ADMIN_ANDY && admin_one != &ADMIN_FANMOCHENG {
             msg!("admin error");
             return Err(ProgramError::InvalidArgument);
         }
-        let admin_two = self.admin_other.key;
-        if admin_two != &ADMIN_ANDY && admin_two != &ADMIN_FANMOCHENG {
-            msg!("admin error");
-            return Err(ProgramError::InvalidArgument);
-        }
 
         // Check signer
-        check_signer(self.admin_signer).unwrap();
+        check_signer(self.admin_signer)?;
         msg!("fee_payer signature ok");
 
+        check_account_key(self.vault, &return_vault_key().0)?;
+        check_account_owner(self.vault, &crate::ID)?;
+        msg!("vault ok");
+
+        check_account_key(self.config, &return_config_key().0)?;
+        msg!("config id ok");
+
         Ok(())
     }
 }
@@ -70,12 +91,82 @@ pub fn process_extract_admin<'a, 'b: 'a>(
     let accounts = Accounts::parse(accounts)?;
     accounts.check()?;
 
-    let transfer_out_lamports = share_with_cap(params.extraction, 500_000_000)?;
+    let config = ConfigRecord::load(accounts.config)?;
+    let treasury = config.treasury();
+    if accounts.recipients.len() != treasury.len() * 2 {
+        msg!("should provide {} recipients with their payout records", treasury.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    // rounding dust stays with the revenue
+    let cuts = treasury
+        .iter()
+        .map(|share| treasury_cut(params.extraction, share))
+        .collect::<Result<Vec<_>, _>>()?;
+    let total = cuts.iter().try_fold(0u64, |sum, cut| math::add(sum, *cut))?;
 
-    **accounts.vault.try_borrow_mut_lamports()? -= transfer_out_lamports * 2;
+    // only revenue can go, never what users are owed
+    update_vault_record(accounts.admin_signer, accounts.vault, accounts.system_program, |vault_record| {
+        let rent_exempt = Rent::get()?.minimum_balance(VaultRecord::LEN);
+        let free = vault_record.free_balance(accounts.vault.lamports(), rent_exempt);
+        if total > free {
+            msg!("only {} lamports of revenue are free", free);
+            return Err(ProgramError::InsufficientFunds);
+        }
+        vault_record.revenue = math::sub(vault_record.revenue, total)?;
+        Ok(())
+    })?;
 
-    **accounts.admin_signer.try_borrow_mut_lamports()? += transfer_out_lamports;
-    **accounts.admin_other.try_borrow_mut_lamports()? += transfer_out_lamports;
+    let now = get_now_time()?;
+    for ((share, cut), pair) in treasury.iter().zip(cuts).zip(accounts.recipients.chunks(2)) {
+        let (recipient, payout_record) = (&pair[0], &pair[1]);
+        check_account_key(recipient, &share.recipient)?;
+
+        pay_from_vault(accounts.vault, recipient, cut)?;
+        record_payout(&accounts, payout_record, &share.recipient, cut, now)?;
+        msg!("pay {} to {}", cut, share.recipient);
+    }
+    msg!("withdraw {} revenue", total);
+
+    Ok(())
+}
+
+pub fn treasury_cut(extraction: u64, share: &TreasuryShare) -> Result<u64, ProgramError> {
+    let cut = (extraction as u128)
+        .checked_mul(share.bps as u128)
+        .ok_or(ProgramError::InvalidArgument)?
+        / TREASURY_BPS as u128;
+    u64::try_from(cut).map_err(|_| ProgramError::InvalidArgument)
+}
+
+fn record_payout<'b>(
+    accounts: &Accounts<'_, AccountInfo<'b>>,
+    payout_record: &AccountInfo<'b>,
+    recipient: &Pubkey,
+    amount: u64,
+    now: i64,
+) -> ProgramResult {
+
+    let (payout_key, bump) = get_treasury_payout_key(recipient);
+    check_account_key(payout_record, &payout_key)?;
+
+    let mut record = if payout_record.data_is_empty() {
+        create_pda_account(
+            accounts.admin_signer,
+            payout_record,
+            accounts.system_program,
+            TreasuryPayoutRecord::LEN,
+            &[b"treasury_payout", recipient.as_ref(), &[bump]],
+        )?;
+        TreasuryPayoutRecord { recipient: *recipient, paid: 0, last_paid_at: 0 }
+    } else {
+        check_account_owner(payout_record, &crate::ID)?;
+        TreasuryPayoutRecord::unpack_from_slice(&payout_record.data.borrow())?
+    };
+
+    record.paid = math::add(record.paid, amount)?;
+    record.last_paid_at = now;
+    record.pack_into_slice(&mut payout_record.data.borrow_mut());
 
     Ok(())
-}
\ No newline at end of file
+}
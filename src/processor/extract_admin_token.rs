@@ -0,0 +1,180 @@
+//! Withdraw the protocol's revenue in an SPL token, split by the treasury table
+
+use web3_utils::{
+    check::{check_account_key, check_account_owner, check_signer},
+    BorshSize, InstructionsAccount,
+    borsh_size::BorshSize,
+    accounts::InstructionsAccount,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
+};
+use spl_associated_token_account_client::{
+    address::get_associated_token_address, instruction::create_associated_token_account_idempotent
+};
+
+use crate::{
+    constants::{ADMIN_ANDY, ADMIN_FANMOCHENG, return_config_key, return_vault_key},
+    processor::extract_admin::treasury_cut,
+    state::{config::ConfigRecord, vault::VaultRecord},
+    utils::{math, token::{get_vault_token_key, transfer_token_from_vault}, vault::update_vault_record},
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+/// The required parameters for the `withdraw admin token` instruction
+pub struct Params {
+    /// In the token's base units
+    pub extraction: u64
+}
+
+
+#[derive(InstructionsAccount)]
+/// The required accounts for the `withdraw admin token` instruction
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// The SPL token program
+    pub token_program: &'a T,
+    /// The associated token account program
+    pub associated_token_program: &'a T,
+    #[cons(writable, signer)]
+    pub admin_signer: &'a T,
+    #[cons(writable)]
+    pub vault: &'a T,
+    /// the registrar config, holding the treasury table
+    pub config: &'a T,
+    /// the token the revenue was earned in
+    pub mint: &'a T,
+    /// the vault's associated token account
+    #[cons(writable)]
+    pub vault_token_account: &'a T,
+    /// (recipient, recipient's associated token account) pairs in the treasury table's order,
+    /// a missing token account is created at the admin's expense
+    #[cons(writable)]
+    pub recipients: &'a [T],
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            associated_token_program: next_account_info(accounts_iter)?,
+            admin_signer: next_account_info(accounts_iter)?,
+            vault: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+            mint: next_account_info(accounts_iter)?,
+            vault_token_account: next_account_info(accounts_iter)?,
+            recipients: accounts_iter.as_slice(),
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        check_account_key(self.token_program, &spl_token::ID)?;
+        check_account_key(self.associated_token_program, &spl_associated_token_account_client::program::ID)?;
+        msg!("program ids ok");
+
+        let admin_one = self.admin_signer.key;
+        if admin_one != &ADMIN_ANDY && admin_one != &ADMIN_FANMOCHENG {
+            msg!("admin error");
+            return Err(ProgramError::InvalidArgument);
+        }
+        check_signer(self.admin_signer)?;
+        msg!("admin signature ok");
+
+        check_account_key(self.vault, &return_vault_key().0)?;
+        check_account_owner(self.vault, &crate::ID)?;
+        check_account_owner(self.mint, &spl_token::ID)?;
+        check_account_key(self.vault_token_account, &get_vault_token_key(self.mint.key))?;
+        msg!("vault token account ok");
+
+        check_account_key(self.config, &return_config_key().0)?;
+        msg!("config id ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_extract_admin_token<'a, 'b: 'a>(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let config = ConfigRecord::load(accounts.config)?;
+    let treasury = config.treasury();
+    if accounts.recipients.len() != treasury.len() * 2 {
+        msg!("should provide {} recipients with their token accounts", treasury.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+
+    // rounding dust stays with the revenue
+    let cuts = treasury
+        .iter()
+        .map(|share| treasury_cut(params.extraction, share))
+        .collect::<Result<Vec<_>, _>>()?;
+    let total = cuts.iter().try_fold(0u64, |sum, cut| math::add(sum, *cut))?;
+
+    // only token revenue can go, never the token profit users are owed
+    let earned = VaultRecord::unpack_from_slice(&accounts.vault.data.borrow())?
+        .token_revenue
+        .iter()
+        .find(|slot| &slot.mint == accounts.mint.key)
+        .map(|slot| slot.revenue)
+        .unwrap_or(0);
+    if total > earned {
+        msg!("only {} of {} revenue is free", earned, accounts.mint.key);
+        return Err(ProgramError::InsufficientFunds);
+    }
+    update_vault_record(accounts.admin_signer, accounts.vault, accounts.system_program, |vault| {
+        vault.spend_token_revenue(accounts.mint.key, total)
+    })?;
+
+    for ((share, cut), pair) in treasury.iter().zip(cuts).zip(accounts.recipients.chunks(2)) {
+        let (recipient, recipient_token_account) = (&pair[0], &pair[1]);
+        check_account_key(recipient, &share.recipient)?;
+        check_account_key(
+            recipient_token_account,
+            &get_associated_token_address(&share.recipient, accounts.mint.key),
+        )?;
+
+        invoke(
+            &create_associated_token_account_idempotent(
+                accounts.admin_signer.key,
+                recipient.key,
+                accounts.mint.key,
+                accounts.token_program.key,
+            ),
+            &[
+                accounts.admin_signer.clone(),
+                recipient_token_account.clone(),
+                recipient.clone(),
+                accounts.mint.clone(),
+                accounts.system_program.clone(),
+                accounts.token_program.clone(),
+                accounts.associated_token_program.clone(),
+            ],
+        )?;
+
+        transfer_token_from_vault(
+            accounts.token_program,
+            accounts.vault_token_account,
+            recipient_token_account,
+            accounts.vault,
+            cut,
+        )?;
+        msg!("pay {} of {} to {}", cut, accounts.mint.key, share.recipient);
+    }
+    msg!("withdraw {} token revenue", total);
+
+    Ok(())
+}
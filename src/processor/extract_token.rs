@@ -0,0 +1,151 @@
+use web3_utils::{
+    BorshSize, InstructionsAccount, accounts::InstructionsAccount, borsh_size::BorshSize, check::{check_account_key, check_account_owner, check_signer}
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    program_pack::Pack,
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{constants::return_vault_key, state::{ReferrerRecordHeader, get_referrer_record_key, ledger::LedgerKind}, utils::{ledger::{append_ledger, ledger_entry}, math, share_with_cap, token::{get_vault_token_key, transfer_token_from_vault}, vault::update_vault_record}};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+/// The required parameters for the `withdraw token` instruction
+pub struct Params {
+    pub extraction: u64
+}
+
+
+#[derive(InstructionsAccount)]
+/// The required accounts for the `withdraw token` instruction
+pub struct Accounts<'a, T> {
+    /// the record's owner, signs unless its withdraw authority does
+    #[cons(writable)]
+    pub user: &'a T,
+    #[cons(writable)]
+    pub user_referrer_record: &'a T,
+    /// the record's profit ledger
+    #[cons(writable)]
+    pub user_ledger: &'a T,
+    /// vault, books the withdrawal fee as token revenue
+    #[cons(writable)]
+    pub vault: &'a T,
+    /// the token the profit was earned in
+    pub mint: &'a T,
+    /// the vault's associated token account
+    #[cons(writable)]
+    pub vault_token_account: &'a T,
+    /// where the tokens go, a token account of the user for the mint
+    #[cons(writable)]
+    pub user_token_account: &'a T,
+    /// The SPL token program
+    pub token_program: &'a T,
+    /// The system program account -- to grow the ledger and the vault
+    pub system_program: &'a T,
+    /// the user's withdraw authority, when it signs instead of the user
+    #[cons(writable, signer)]
+    pub authority: Option<&'a T>,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            user: next_account_info(accounts_iter)?,
+            user_referrer_record: next_account_info(accounts_iter)?,
+            user_ledger: next_account_info(accounts_iter)?,
+            vault: next_account_info(accounts_iter)?,
+            mint: next_account_info(accounts_iter)?,
+            vault_token_account: next_account_info(accounts_iter)?,
+            user_token_account: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+            authority: next_account_info(accounts_iter).ok(),
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_owner(self.user_referrer_record, &crate::ID)?;
+        check_account_key(self.token_program, &spl_token::ID)?;
+        msg!("token program id ok");
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+
+        check_signer(self.signer())?;
+        msg!("signature ok");
+
+        Ok(())
+    }
+
+    /// Who signs the withdrawal and pays any rent it needs
+    pub fn signer(&self) -> &'a AccountInfo<'b> {
+        self.authority.unwrap_or(self.user)
+    }
+}
+
+pub fn process_extract_token<'a, 'b: 'a>(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    msg!("use withdraw {} of {}", params.extraction, accounts.mint.key);
+
+    let (referrer_key, _) = get_referrer_record_key(accounts.user.key);
+    check_account_key(accounts.user_referrer_record, &referrer_key)?;
+    msg!("referrer key ok");
+
+    let (vault_key, _) = return_vault_key();
+    check_account_key(accounts.vault, &vault_key)?;
+    check_account_key(accounts.vault_token_account, &get_vault_token_key(accounts.mint.key))?;
+    msg!("vault key ok");
+
+    // the tokens go to the user, whoever signs
+    check_account_owner(accounts.user_token_account, &spl_token::ID)?;
+    let user_token_account = spl_token::state::Account::unpack(&accounts.user_token_account.data.borrow())?;
+    if &user_token_account.owner != accounts.user.key {
+        msg!("token account is owned by {}", user_token_account.owner);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let signer = accounts.signer();
+    let mut record_data =
+        ReferrerRecordHeader::unpack_from_slice(&accounts.user_referrer_record.data.borrow())?;
+    if !record_data.can_withdraw(accounts.user.key, signer.key) {
+        msg!("{} can't withdraw for {}", signer.key, accounts.user.key);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // no rent to keep in a token account, the whole balance can go
+    record_data.sub_token_profit(accounts.mint.key, params.extraction)?;
+
+    let real_ex = share_with_cap(params.extraction, 990_000_000)?;
+
+    transfer_token_from_vault(
+        accounts.token_program,
+        accounts.vault_token_account,
+        accounts.user_token_account,
+        accounts.vault,
+        real_ex,
+    )?;
+    msg!("transfer ok");
+
+    update_vault_record(signer, accounts.vault, accounts.system_program, |vault| {
+        vault.earn_token(accounts.mint.key, math::sub(params.extraction, real_ex)?)
+    })?;
+
+    record_data.try_pack_into_slice(&mut accounts.user_referrer_record.data.borrow_mut())?;
+
+    append_ledger(
+        signer,
+        accounts.system_program,
+        accounts.user_referrer_record,
+        accounts.user_ledger,
+        &ledger_entry(LedgerKind::Withdrawal, Pubkey::default(), *accounts.mint.key, params.extraction)?,
+    )
+}
@@ -15,7 +15,7 @@ use solana_program::{
 use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
 
 
-use crate::{central_state, constants::return_vault_key, state::{NameStateRecordHeader, get_name_state_key, vault::VaultRecord}, utils::{can_settle, get_hashed_name}};
+use crate::{central_state, constants::{return_config_key, return_vault_key}, state::{NameStateRecordHeader, ReferrerRecordHeader, RegistrationRecordHeader, config::ConfigRecord, get_name_state_key, leaderboard::LeaderboardKind, primary_name::{PrimaryNameRecord, get_primary_name_key}, get_referrer_record_key, get_registration_key, ledger::{LedgerKind, get_ledger_key}, wrapped::get_wrapped_name_key}, utils::{REGISTRATION_PERIOD, auction_index::{check_listing, unindex_auction}, bid_history::close_bid_history, can_settle, portfolio::{prune_portfolio, update_portfolio}, create_pda_account, get_hashed_name, get_now_time, math, next_account_slice, share_with_cap, leaderboard::submit_to_leaderboard, ledger::ledger_entry, stats::record_stats, transfer_by_chain::{ReferrerChain, add_profit}, vault::update_vault_record, wrapped::{check_retire_accounts, resolve_seller, retire_wrapped_name}}};
 
 pub mod initialize;
 pub mod repeat;
@@ -25,6 +25,8 @@ pub mod repeat;
 pub struct Params {
     pub domain_name: String,
     pub custom_price: Option<u64>,
+    /// how many accounts `referrer_records` takes, a record and its ledger per level
+    pub referrer_accounts: u8,
 }
 
 #[derive(InstructionsAccount)]
@@ -48,42 +50,92 @@ pub struct Accounts<'a, T> {
     #[cons(writable, signer)]
     pub fee_payer: &'a T,
     /// name account owner -- The initialized domain name can be arbitrary
-    /// Domain names auctioned more than twice must be the same as in the records
+    /// Domain names auctioned more than twice must be the same as in the records,
+    /// or hold the token of a wrapped domain
     #[cons(writable)]
     pub origin_name_account_owner: &'a T,
     #[cons(writable)]
     pub origin_name_owner_record: &'a T,
+    /// origin owner's profit ledger
+    #[cons(writable)]
+    pub origin_name_owner_ledger: &'a T,
     /// vault
     #[cons(writable)]
     pub vault: &'a T,
-    /// new domain owner
-    pub new_domain_owner: &'a T,
-    /// new owner's referrer record
+    /// the domain's registration record -- created on first settlement
     #[cons(writable)]
-    pub referrer_record: &'a T,
-    /// buyer's referrer -- we named A
+    pub registration_record: &'a T,
+    /// who opened the auction, the state's rent goes back to it
+    /// -- the vault for states that don't record an opener
+    #[cons(writable)]
+    pub opener: &'a T,
+    /// the registrar config
+    pub config: &'a T,
+    /// the domain leaderboard -- left alone until the admins create it
+    #[cons(writable)]
+    pub domain_leaderboard: &'a T,
+    /// the referrer leaderboard -- left alone until the admins create it
+    #[cons(writable)]
+    pub referrer_leaderboard: &'a T,
+    /// the protocol statistics -- left alone until the admins create them
+    #[cons(writable)]
+    pub stats: &'a T,
+    /// the root's auction index
+    #[cons(writable)]
+    pub auction_index: &'a T,
+    /// the index page listing the auction -- it is taken off
+    #[cons(writable)]
+    pub auction_page: &'a T,
+    /// the new owner's portfolio -- the bid becomes a domain
+    #[cons(writable)]
+    pub new_owner_portfolio: &'a T,
+    /// the current name owner's portfolio -- the domain is taken off it, unused on a first sale
+    #[cons(writable)]
+    pub origin_owner_portfolio: &'a T,
+    /// the current name owner's primary name record -- cleared if it names this domain
     #[cons(writable)]
-    pub referrer_a: &'a T,
-    /// A's referrer record
+    pub origin_owner_primary_name: &'a T,
+    /// the domain's wrapped record -- closed for a wrapped domain, unused otherwise
     #[cons(writable)]
-    pub referrer_a_record: Option<&'a T>,
-    /// A's referrer -- named B
+    pub wrapped_name: &'a T,
+    /// the current owner's token account for a wrapped domain -- frozen, unused otherwise
     #[cons(writable)]
-    pub referrer_b: Option<&'a T>,
-    /// B's referrer record
+    pub origin_owner_token_account: &'a T,
+    /// the wrapped domain's mint -- unused for a domain that isn't wrapped
+    pub wrapped_mint: &'a T,
+    /// The token program account
+    pub token_program: &'a T,
+    /// the auction's bid history -- closed with the state, its rent goes to the opener
     #[cons(writable)]
-    pub referrer_b_record: Option<&'a T>,
-    /// B's referrer -- named C
+    pub bid_history: &'a T,
+    /// the settler's referrer record, credited with the settlement tip -- may be empty
     #[cons(writable)]
-    pub referrer_c: Option<&'a T>,
-    /// C's referrer record
+    pub settler_record: &'a T,
+    /// the settler's profit ledger
     #[cons(writable)]
-    pub referrer_c_record: Option<&'a T>,
+    pub settler_ledger: &'a T,
+    /// new domain owner
+    pub new_domain_owner: &'a T,
+    /// new owner's referrer record
+    #[cons(writable)]
+    pub referrer_record: &'a T,
+    /// the referrers' records, each followed by its ledger, nearest first,
+    /// until the vault or the configured depth
+    #[cons(writable)]
+    pub referrer_records: &'a [T],
 }
 
 impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    /// accounts before the referrers' records
+    pub const FIXED_LEN: usize = 31;
+    /// positions of the accounts a batch passes once for all its items: the programs, root,
+    /// central state, the settler with its record and ledger, vault, config, both leaderboards,
+    /// stats and the auction index
+    pub const SHARED: [usize; 14] = [0, 1, 4, 5, 6, 10, 13, 14, 15, 16, 17, 25, 27, 28];
+
     pub fn parse(
         accounts: &'a [AccountInfo<'b>],
+        referrer_accounts: u8,
     ) -> Result<Self, ProgramError> {
         let accounts_iter = &mut accounts.iter();
         Ok(Accounts {
@@ -96,15 +148,29 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
             fee_payer: next_account_info(accounts_iter)?,
             origin_name_account_owner: next_account_info(accounts_iter)?,
             origin_name_owner_record: next_account_info(accounts_iter)?,
+            origin_name_owner_ledger: next_account_info(accounts_iter)?,
             vault: next_account_info(accounts_iter)?,
+            registration_record: next_account_info(accounts_iter)?,
+            opener: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+            domain_leaderboard: next_account_info(accounts_iter)?,
+            referrer_leaderboard: next_account_info(accounts_iter)?,
+            stats: next_account_info(accounts_iter)?,
+            auction_index: next_account_info(accounts_iter)?,
+            auction_page: next_account_info(accounts_iter)?,
+            new_owner_portfolio: next_account_info(accounts_iter)?,
+            origin_owner_portfolio: next_account_info(accounts_iter)?,
+            origin_owner_primary_name: next_account_info(accounts_iter)?,
+            wrapped_name: next_account_info(accounts_iter)?,
+            origin_owner_token_account: next_account_info(accounts_iter)?,
+            wrapped_mint: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            bid_history: next_account_info(accounts_iter)?,
+            settler_record: next_account_info(accounts_iter)?,
+            settler_ledger: next_account_info(accounts_iter)?,
             new_domain_owner:next_account_info(accounts_iter)?,
             referrer_record: next_account_info(accounts_iter)?,
-            referrer_a: next_account_info(accounts_iter)?,
-            referrer_a_record: next_account_info(accounts_iter).ok(),
-            referrer_b: next_account_info(accounts_iter).ok(),
-            referrer_b_record: next_account_info(accounts_iter).ok(),
-            referrer_c: next_account_info(accounts_iter).ok(),
-            referrer_c_record: next_account_info(accounts_iter).ok(),
+            referrer_records: next_account_slice(accounts_iter, referrer_accounts)?,
         })
     }
 
@@ -114,6 +180,10 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
         msg!("nameservice id ok");
         check_account_key(self.system_program, &solana_program::system_program::ID)?;
         msg!("system_program id ok");
+        check_account_key(self.config, &return_config_key().0)?;
+        msg!("config id ok");
+        check_account_key(self.settler_record, &get_referrer_record_key(self.fee_payer.key).0)?;
+        msg!("settler record ok");
 
         check_account_owner(self.root_domain, &web3_domain_name_service::ID)?;
         check_account_owner(self.name, &web3_domain_name_service::ID)?;
@@ -124,6 +194,36 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
 
         Ok(())
     }
+
+    pub fn referrer_chain(&self, mint: Pubkey) -> Result<ReferrerChain<'a, 'b>, ProgramError> {
+        let config = ConfigRecord::load(self.config)?;
+        Ok(ReferrerChain {
+            mint,
+            payer: self.fee_payer,
+            system_program: self.system_program,
+            vault: self.vault,
+            referrer_leaderboard: self.referrer_leaderboard,
+            usr: self.new_domain_owner,
+            usr_record: self.referrer_record,
+            domain: *self.name.key,
+            referrer_records: self.referrer_records,
+            shares: config.referral_shares().to_vec(),
+            tiers: config.tiers().to_vec(),
+        })
+    }
+}
+
+/// Everything a settlement needs, read before any account is written
+pub struct Settlement {
+    pub name_state_data: NameStateRecordHeader,
+    pub name_account_key: Pubkey,
+    pub name_record: NameRecordHeader,
+    /// who the domain is sold by -- its owner, or the holder of its token
+    pub seller: Pubkey,
+    pub registration: Option<RegistrationRecordHeader>,
+    pub registration_seeds: Vec<u8>,
+    /// whether the registrar holds the domain for a token
+    pub wrapped: bool,
 }
 
 // all pepole on the referrer chain can confirm the domain
@@ -134,9 +234,20 @@ pub fn process_finalize_name<'a, 'b: 'a>(
     params: Params,
 ) -> ProgramResult {
 
-    let accounts = Accounts::parse(accounts)?;
+    let accounts = Accounts::parse(accounts, params.referrer_accounts)?;
     accounts.check()?;
 
+    let settlement = prepare_finalize_name(&accounts, &params)?;
+    settle_name(&accounts, params, settlement)
+}
+
+/// Validate a settlement without writing any account,
+/// so a batch can skip an item that would fail here
+pub fn prepare_finalize_name(
+    accounts: &Accounts<'_, AccountInfo<'_>>,
+    params: &Params,
+) -> Result<Settlement, ProgramError> {
+
     let name_state_account = accounts.domain_state_account;
     let hased_name = get_hashed_name(&params.domain_name);
 
@@ -151,68 +262,362 @@ pub fn process_finalize_name<'a, 'b: 'a>(
     let (vault_key, _) = return_vault_key();
     check_account_key(vault, &vault_key)?;
 
-    {    
-        let name_state_data = 
-            NameStateRecordHeader::unpack_from_slice(&name_state_account.data.borrow())?;
-        
-        // after auction time 
-        if !can_settle(name_state_data.update_time)?{
-            msg!("not settle time");
-            return Err(ProgramError::InvalidArgument);
-        }
-        
-        // settle_qualifications_verify(&accounts, &name_state_data.highest_bidder)?;
-        msg!("permissionless settle");
-
-        let domain_name_account = accounts.name;
-        let (name_account_key, _) = get_seeds_and_key(
-            accounts.naming_service_program.key, 
-            hased_name.clone(), 
-            None, 
-            Some(accounts.root_domain.key)
-        );
-        check_account_key(domain_name_account, &name_account_key)?;
-        msg!("name account key ok");
+    let (registration_key, registration_seeds) = get_registration_key(
+        &params.domain_name,
+        accounts.root_domain.key
+    );
+    check_account_key(accounts.registration_record, &registration_key)?;
+    msg!("registration record key ok");
+
+    let name_state_data = 
+        NameStateRecordHeader::unpack_from_slice(&name_state_account.data.borrow())?;
     
-        let name_record = 
-            NameRecordHeader::unpack_from_slice(&domain_name_account.data.borrow())?;
-
-        if name_record.owner == central_state::KEY {
-            msg!("frist create");
-            initialize::initialize_settle(
-                &accounts, 
-                params, 
-                &name_state_data, 
-            )?;
-        }else {
-            repeat::repeat_settle(
-                &accounts, 
-                params, 
-                name_record, 
-                &name_state_data, 
+    // states opened before the opener was recorded paid the rent out of the price
+    let rent_receiver = if name_state_data.opener == Pubkey::default() {
+        vault_key
+    } else {
+        name_state_data.opener
+    };
+    check_account_key(accounts.opener, &rent_receiver)?;
+    msg!("opener ok");
+
+    // after auction time 
+    if !can_settle(name_state_data.end_time)?{
+        msg!("not settle time");
+        return Err(ProgramError::InvalidArgument);
+    }
+    
+    // anyone can settle, the name still goes to whoever won it
+    check_winner(accounts.new_domain_owner, &name_state_data)?;
+    msg!("permissionless settle");
+
+    let domain_name_account = accounts.name;
+    let (name_account_key, _) = get_seeds_and_key(
+        accounts.naming_service_program.key, 
+        hased_name, 
+        None, 
+        Some(accounts.root_domain.key)
+    );
+    check_account_key(domain_name_account, &name_account_key)?;
+    msg!("name account key ok");
+
+    let name_record = 
+        NameRecordHeader::unpack_from_slice(&domain_name_account.data.borrow())?;
+
+    let registration = if accounts.registration_record.data_is_empty() {
+        None
+    } else {
+        Some(RegistrationRecordHeader::unpack_from_slice(&accounts.registration_record.data.borrow())?)
+    };
+
+    // a reclaimed name has no seller, its token may be long gone
+    let seller = if registration.as_ref().is_some_and(|r| r.is_reclaimed()) {
+        name_record.owner
+    } else {
+        resolve_seller(&name_account_key, &name_record.owner, accounts.wrapped_name, accounts.origin_owner_token_account)?
+    };
+
+    let wrapped = name_record.owner == get_wrapped_name_key(&name_account_key).0;
+    if wrapped {
+        check_retire_accounts(&name_account_key, accounts.wrapped_name, accounts.wrapped_mint, accounts.token_program)?;
+    }
+
+    // everything the payout checks on its way, so it can't fail halfway
+    accounts.referrer_chain(name_state_data.mint)?.validate()?;
+    check_listing(accounts.root_domain.key, accounts.auction_page, name_state_data.auction_page, name_state_account.key)?;
+    if name_record.owner != central_state::KEY && !registration.as_ref().is_some_and(|r| r.is_reclaimed()) {
+        check_account_key(accounts.origin_name_account_owner, &seller)?;
+        check_account_key(accounts.origin_name_owner_record, &get_referrer_record_key(&seller).0)?;
+        check_account_key(accounts.origin_name_owner_ledger, &get_ledger_key(accounts.origin_name_owner_record.key).0)?;
+    }
+    if !accounts.settler_record.data_is_empty() {
+        check_account_key(accounts.settler_ledger, &get_ledger_key(accounts.settler_record.key).0)?;
+    }
+
+    Ok(Settlement {
+        name_state_data,
+        name_account_key,
+        name_record,
+        seller,
+        registration,
+        registration_seeds,
+        wrapped,
+    })
+}
+
+/// Pay out, hand the domain to the highest bidder and close the auction state
+pub fn settle_name(
+    accounts: &Accounts<'_, AccountInfo<'_>>,
+    params: Params,
+    settlement: Settlement,
+) -> ProgramResult {
+
+    let Settlement {
+        name_state_data,
+        name_account_key,
+        name_record,
+        seller,
+        registration,
+        registration_seeds,
+        wrapped,
+    } = settlement;
+
+    // a lapsed name taken back by the registrar settles like a fresh one:
+    // the lapsed owner gets nothing and the whole price goes through the referrer chain
+    let reclaimed = registration.as_ref().is_some_and(|r| r.is_reclaimed());
+
+    // the settler is tipped off the top, everyone else shares what is left
+    let mut proceeds = name_state_data.highest_price;
+    // what the price turned into user profit, the rest is the protocol's
+    let mut credited = 0;
+    if accounts.settler_record.data_is_empty() {
+        msg!("settler has no referrer record, no tip");
+    } else {
+        let config = ConfigRecord::load(accounts.config)?;
+        let tip = share_with_cap(name_state_data.highest_price, config.settle_tip_rate)?;
+        if tip > 0 {
+            add_profit(
+                accounts.settler_record,
+                accounts.settler_ledger,
+                &ledger_entry(LedgerKind::SettleTip, name_account_key, name_state_data.mint, tip)?,
+                accounts.fee_payer,
+                accounts.system_program,
             )?;
+            proceeds = math::sub(proceeds, tip)?;
+            credited = tip;
+            msg!("settle tip: {:?}", tip);
         }
+    }
+
+    let previous_owner = name_record.owner;
+    let first_sale = previous_owner == central_state::KEY || reclaimed;
+    let settled = if first_sale {
+        msg!("frist create");
+        initialize::initialize_settle(
+            accounts, 
+            params, 
+            &name_state_data, 
+            proceeds,
+        )?
+    }else {
+        repeat::repeat_settle(
+            accounts, 
+            params, 
+            &seller, 
+            &name_state_data, 
+            proceeds,
+        )?
+    };
+    credited = math::add(credited, settled)?;
+
+    // the token no longer stands for the domain: the seller's is frozen and the record closed,
+    // a reclaimed domain's token may be long gone and the record's rent goes to the vault
+    if wrapped {
+        let (holder_token_account, rent_receiver) = if reclaimed {
+            (None, accounts.vault)
+        } else {
+            (Some(accounts.origin_owner_token_account), accounts.origin_name_account_owner)
+        };
+        retire_wrapped_name(
+            accounts.wrapped_name,
+            accounts.wrapped_mint,
+            holder_token_account,
+            accounts.token_program,
+            accounts.central_state,
+            rent_receiver,
+        )?;
+    }
+
+    // a resale hands over the term as it stands, even one already in its grace period,
+    // everything else starts a new one
+    let now = get_now_time()?;
+    let expire_time = match registration {
+        Some(r) if !r.is_reclaimed() => r.expire_time,
+        _ => now.checked_add(REGISTRATION_PERIOD).ok_or(ProgramError::InvalidArgument)?,
+    };
+    if accounts.registration_record.data_is_empty() {
+        create_pda_account(
+            accounts.fee_payer,
+            accounts.registration_record,
+            accounts.system_program,
+            RegistrationRecordHeader::LEN,
+            &registration_seeds.chunks(32).collect::<Vec<&[u8]>>(),
+        )?;
+    }
+    RegistrationRecordHeader::new(*accounts.new_domain_owner.key, expire_time)
+        .pack_into_slice(&mut accounts.registration_record.data.borrow_mut());
+    msg!("registration expires at {}", expire_time);
+
+    count_invitee_domain(accounts)?;
+
+    // the domain leaves whoever held it, if anyone did
+    if previous_owner != central_state::KEY {
+        prune_portfolio(accounts.origin_owner_portfolio, &seller, |portfolio| {
+            portfolio.remove_domain(&name_account_key)
+        })?;
+        invalidate_primary_name(accounts.origin_owner_primary_name, &seller, &name_account_key)?;
+    }
+    let name_state_key = *accounts.domain_state_account.key;
+    update_portfolio(
+        accounts.fee_payer,
+        accounts.system_program,
+        accounts.new_owner_portfolio,
+        accounts.new_domain_owner.key,
+        |portfolio| {
+            portfolio.remove_bid(&name_state_key);
+            portfolio.add_domain(name_account_key);
+        },
+    )?;
 
+    unindex_auction(
+        accounts.root_domain.key,
+        accounts.auction_index,
+        accounts.auction_page,
+        name_state_data.auction_page,
+        accounts.domain_state_account.key,
+    )?;
+
+    {
         let lamports = **accounts.domain_state_account.lamports.borrow();
         **accounts.domain_state_account.try_borrow_mut_lamports()? -= lamports;
-        **accounts.vault.try_borrow_mut_lamports()? += lamports;
+        **accounts.opener.try_borrow_mut_lamports()? += lamports;
+        msg!("return state rent: {} to {}", lamports, accounts.opener.key);
         
         let mut data = accounts.domain_state_account.try_borrow_mut_data()?;
             for byte in data.iter_mut() {
                 *byte = 0;
         }
         accounts.domain_state_account.assign(&solana_program::system_program::ID);
+    }
+    close_bid_history(accounts.bid_history, &name_state_key, accounts.opener)?;
+
+    if !name_state_data.is_token_auction() {
+        submit_to_leaderboard(
+            accounts.domain_leaderboard,
+            LeaderboardKind::Domains,
+            name_account_key,
+            name_state_data.highest_price,
+        )?;
+    }
+
+    // token prices don't add up with lamport ones, the settlement still counts
+    let volume = if name_state_data.is_token_auction() { 0 } else { name_state_data.highest_price };
+    let resales = u64::from(!first_sale);
+    record_stats(accounts.stats, |stats, now| {
+        stats.settled_volume = math::add(stats.settled_volume, volume)?;
+        stats.settlements = math::add(stats.settlements, 1)?;
+        stats.resales = math::add(stats.resales, resales)?;
+        // auctions opened before the stats existed were never counted
+        stats.active_auctions = stats.active_auctions.saturating_sub(1);
+
+        let today = stats.today(now);
+        today.settled_volume = math::add(today.settled_volume, volume)?;
+        today.settlements = math::add(today.settlements, 1)?;
+        today.resales = math::add(today.resales, resales)?;
+        Ok(())
+    })?;
 
-        let mut vault_record = 
-            VaultRecord::unpack_from_slice(&accounts.vault.data.borrow())?;
-        vault_record.update_top_domain(name_account_key, name_state_data.highest_price);
+    update_vault_record(accounts.fee_payer, accounts.vault, accounts.system_program, |vault_record| {
+        // token prices don't rank against lamport prices, nor do they pass through the vault's lamports
+        if !name_state_data.is_token_auction() {
+            vault_record.update_top_domain(name_account_key, name_state_data.highest_price);
+
+            // the bid held for the auction becomes profit owed to users and revenue
+            vault_record.settle_owed(name_state_data.highest_price);
+            vault_record.owe(credited)?;
+            vault_record.earn(math::sub(name_state_data.highest_price, credited)?)?;
+        } else {
+            // what the shares left of the bid in the vault's token account is the protocol's
+            vault_record.earn_token(&name_state_data.mint, math::sub(name_state_data.highest_price, credited)?)?;
+        }
         vault_record.domain_count = vault_record.domain_count.checked_add(1)
             .ok_or(ProgramError::InvalidArgument)?;
-        
-        // Write the updated vault record back to the account
-        vault_record.pack_into_slice(&mut accounts.vault.data.borrow_mut());
-        msg!("vault record updated ok");
+        Ok(())
+    })?;
+    msg!("vault record updated ok");
+
+    Ok(())
+}
+
+/// A domain that changes hands stops being its previous owner's primary name
+fn invalidate_primary_name(
+    primary_name: &AccountInfo,
+    owner: &Pubkey,
+    name_account: &Pubkey,
+) -> ProgramResult {
+
+    check_account_key(primary_name, &get_primary_name_key(owner).0)?;
+    if primary_name.data_is_empty() {
+        return Ok(());
+    }
+    check_account_owner(primary_name, &crate::ID)?;
+
+    let mut record = PrimaryNameRecord::unpack(&primary_name.data.borrow())?;
+    if &record.name_account == name_account {
+        record.invalidate();
+        record.pack(&mut primary_name.data.borrow_mut());
+        msg!("primary name of {} cleared", owner);
+    }
+
+    Ok(())
+}
+
+/// Count the domain for the new owner's direct referrer, unless that is the vault.
+/// The chain walk already checked the first referrer record.
+fn count_invitee_domain(
+    accounts: &Accounts<'_, AccountInfo<'_>>,
+) -> ProgramResult {
+
+    if accounts.referrer_record.data_is_empty() {
+        return Ok(());
     }
+    let referrer = 
+        ReferrerRecordHeader::unpack_from_slice(&accounts.referrer_record.data.borrow())?.referrer_account;
+    if &referrer == accounts.vault.key {
+        return Ok(());
+    }
+
+    let referrer_record = accounts.referrer_records.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    check_account_key(referrer_record, &get_referrer_record_key(&referrer).0)?;
 
+    // the chain walk grew it to the current layout
+    let mut data_ref = referrer_record.try_borrow_mut_data()?;
+    let mut record_data = ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
+    record_data.downline.invitee_domains = record_data.downline.invitee_domains
+        .checked_add(1)
+        .ok_or(ProgramError::InvalidArgument)?;
+    record_data.try_pack_into_slice(&mut data_ref)?;
+    msg!("add an invitee domain to {}", referrer);
+
+    Ok(())
+}
+
+/// The name can only be settled to the auction's highest bidder
+fn check_winner(new_domain_owner: &AccountInfo, name_state: &NameStateRecordHeader) -> ProgramResult {
+    if new_domain_owner.key != &name_state.highest_bidder {
+        msg!("the auction was won by {}", name_state.highest_bidder);
+        return Err(ProgramError::InvalidArgument);
+    }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::state::AuctionKind;
+
+    #[test]
+    fn only_the_highest_bidder_receives_the_name() {
+        let bidder = Pubkey::new_unique();
+        let name_state = NameStateRecordHeader::new(&bidder, 0, 10_000_000, "web3", "alice", &Pubkey::default(), AuctionKind::Registration);
+
+        let (owner, other) = (Pubkey::default(), Pubkey::new_unique());
+        let (mut lamports, mut data) = (0, vec![]);
+        let outsider = AccountInfo::new(&other, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert!(check_winner(&outsider, &name_state).is_err());
+
+        let (mut lamports, mut data) = (0, vec![]);
+        let winner = AccountInfo::new(&bidder, false, false, &mut lamports, &mut data, &owner, false, 0);
+        assert!(check_winner(&winner, &name_state).is_ok());
+    }
+}
@@ -1,6 +1,6 @@
 
 use solana_program::{
-    account_info::{AccountInfo}, entrypoint::ProgramResult, msg,
+    account_info::{AccountInfo}, msg, program_error::ProgramError,
 };
 
 use web3_utils::check::check_account_key;
@@ -11,14 +11,16 @@ pub fn initialize_settle(
     accounts: &super::Accounts<'_, AccountInfo<'_>>,
     params: super::Params,
     name_state_data: &NameStateRecordHeader,
-) -> ProgramResult {
+    // the price less the settlement tip
+    proceeds: u64,
+) -> Result<u64, ProgramError> {
 
-    msg!("now the price: {:?}, and referrer all", name_state_data.highest_price);
+    msg!("now the price: {:?}, and referrer all: {:?}", name_state_data.highest_price, proceeds);
     let (vault_key, _) = return_vault_key();
     check_account_key(accounts.vault, &vault_key)?;
 
-    transfer_by_referrer_chain(
-        &accounts, name_state_data.highest_price,
+    let credited = transfer_by_referrer_chain(
+        &accounts.referrer_chain(name_state_data.mint)?, proceeds,
     )?;
     msg!("transfer profit and promote ok");
     
@@ -33,5 +35,5 @@ pub fn initialize_settle(
         params.custom_price
     )?;
     
-    Ok(())
+    Ok(credited)
 }
\ No newline at end of file
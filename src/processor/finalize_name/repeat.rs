@@ -1,50 +1,69 @@
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg,  program_error::ProgramError
+    account_info::AccountInfo, msg, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey,
 };
-use web3_domain_name_service::state::NameRecordHeader;
 use web3_utils::check::check_account_key;
-use solana_program::program_pack::Pack;
-use crate::{central_state, cpi::Cpi, state::{NameStateRecordHeader, get_referrer_record_key, ReferrerRecordHeader}, utils::{transfer_by_chain::transfer_by_referrer_chain, share_with_cap}};
+use crate::{central_state, cpi::Cpi, state::{NameStateRecordHeader, ReferrerRecordHeader, get_referrer_record_key, ledger::LedgerKind}, utils::{create_pda_account, get_now_time, ledger::ledger_entry, math, transfer_by_chain::{add_profit, transfer_by_referrer_chain}, share_with_cap, vault::update_vault_record}};
 
 
 // Here we need to consider calls to the same address using different names.
 pub fn repeat_settle(
     accounts: &super::Accounts<'_, AccountInfo<'_>>,
     params: super::Params,
-    name_account_data: NameRecordHeader,
+    // the name's owner, or the holder of its token while it is wrapped
+    seller: &Pubkey,
     name_state_data: &NameStateRecordHeader,
-) -> ProgramResult {
+    // the price less the settlement tip
+    proceeds: u64,
+) -> Result<u64, ProgramError> {
 
-    check_account_key(accounts.origin_name_account_owner, &name_account_data.owner)?;
+    check_account_key(accounts.origin_name_account_owner, seller)?;
 
-    let domain_price = name_state_data.highest_price;
-    msg!("transaction price: {:?}", domain_price);
+    let domain_price = proceeds;
+    msg!("transaction price: {:?}, shared: {:?}", name_state_data.highest_price, domain_price);
 
     // 5%
-    transfer_by_referrer_chain(
-        &accounts, share_with_cap(domain_price, 50_000_000)?
+    let chain_credited = transfer_by_referrer_chain(
+        &accounts.referrer_chain(name_state_data.mint)?, share_with_cap(domain_price, 50_000_000)?
     )?;
     msg!("add referrer profit and performance and up level ok");
 
     let origin_owner = accounts.origin_name_account_owner;
     let origin_owner_referrer_record = accounts.origin_name_owner_record;
 
-    let (origin_owner_referrer_record_key, _) = get_referrer_record_key(origin_owner.key);
+    let (origin_owner_referrer_record_key, origin_owner_referrer_record_seeds) = get_referrer_record_key(origin_owner.key);
     check_account_key(origin_owner_referrer_record, &origin_owner_referrer_record_key)?;
-   
-    let mut data_origin_ref = origin_owner_referrer_record.try_borrow_mut_data()?;
-    let mut origin_owner_record_data = 
-        ReferrerRecordHeader::unpack_from_slice( &data_origin_ref)?;
 
+    // a seller who closed its record, or a token holder who never had one,
+    // gets one under the vault to hold the proceeds
+    if origin_owner_referrer_record.data_is_empty() {
+        create_pda_account(
+            accounts.fee_payer,
+            origin_owner_referrer_record,
+            accounts.system_program,
+            ReferrerRecordHeader::LEN,
+            &origin_owner_referrer_record_seeds.chunks(32).collect::<Vec<&[u8]>>(),
+        )?;
+        ReferrerRecordHeader::new(*accounts.vault.key, get_now_time()?)
+            .try_pack_into_slice(&mut origin_owner_referrer_record.data.borrow_mut())?;
+        update_vault_record(accounts.fee_payer, accounts.vault, accounts.system_program, |vault_record| {
+            vault_record.usr_count = vault_record.usr_count.checked_add(1)
+                .ok_or(ProgramError::InvalidArgument)?;
+            Ok(())
+        })?;
+        msg!("open a record for {} under the vault", origin_owner.key);
+    }
+   
     // the domain origin owner's account will only add profit(95%)
     let get_lamports = share_with_cap(domain_price, 950_000_000)?;
-    origin_owner_record_data.profit =
-        origin_owner_record_data.profit
-        .checked_add(get_lamports)
-        .ok_or(ProgramError::InvalidArgument)?;
-
-    origin_owner_record_data.pack_into_slice(&mut *data_origin_ref);
+    add_profit(
+        origin_owner_referrer_record,
+        accounts.origin_name_owner_ledger,
+        &ledger_entry(LedgerKind::SaleProceeds, *accounts.name.key, name_state_data.mint, get_lamports)?,
+        accounts.fee_payer,
+        accounts.system_program,
+    )?;
     msg!("add origin owner only profit ok: {:?}", get_lamports);
+    let credited = math::add(chain_credited, get_lamports)?;
 
     let central_state_signer_seeds: &[&[u8]] = &[&crate::ID.to_bytes(), &[central_state::NONCE]];
     Cpi::transfer_name_account(
@@ -57,5 +76,5 @@ pub fn repeat_settle(
         params.custom_price
     )?;
 
-    Ok(())
+    Ok(credited)
 }
\ No newline at end of file
@@ -21,7 +21,7 @@ use solana_program::{
 
 use solana_system_interface::instruction as system_instruction;
 
-use crate::{central_state, constants::return_vault_key, processor::init_usr, state::{NameStateRecordHeader, ReferrerRecordHeader, get_name_state_key, get_referrer_record_key}, utils::{get_hashed_name, get_now_time, math, share_with_cap}};
+use crate::{central_state, constants::{return_config_key, return_vault_key}, processor::init_usr, state::{NameStateRecordHeader, bid_history::BidEntry, ReferrerRecordHeader, get_name_state_key, get_referrer_record_key}, utils::{auction_index::{auction_entry, update_indexed_auction}, bid_history::record_bid, get_hashed_name, get_now_time, math, portfolio::{prune_portfolio, update_portfolio}, share_with_cap, token::{TokenPayment, refund_token_bid, transfer_token}, vault::{grow_from_vault, update_vault_record}}};
 use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
@@ -67,6 +67,25 @@ pub struct Accounts<'a, T> {
     /// domain account
     pub domain_name_account: &'a T,
 
+    /// the registrar config
+    pub config: &'a T,
+
+    /// the index page listing the auction -- its price and end time are refreshed
+    #[cons(writable)]
+    pub auction_page: &'a T,
+
+    /// the new bidder's portfolio -- created with its first bid
+    #[cons(writable)]
+    pub bidder_portfolio: &'a T,
+
+    /// the outbid bidder's portfolio
+    #[cons(writable)]
+    pub last_bidder_portfolio: &'a T,
+
+    /// the auction's bid history -- the bid is appended
+    #[cons(writable)]
+    pub bid_history: &'a T,
+
     /// referrer's referrer record account
     pub superior_referrer_record: Option<&'a T>,
 }
@@ -85,6 +104,11 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
             vault: next_account_info(accounts_iter)?,
             referrer_record_account: next_account_info(accounts_iter)?,
             domain_name_account: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+            auction_page: next_account_info(accounts_iter)?,
+            bidder_portfolio: next_account_info(accounts_iter)?,
+            last_bidder_portfolio: next_account_info(accounts_iter)?,
+            bid_history: next_account_info(accounts_iter)?,
             superior_referrer_record: next_account_info(accounts_iter).ok(),
         })
     }
@@ -96,6 +120,7 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
 
         check_account_owner(self.root_domain, &web3_domain_name_service::ID)?;
         check_account_owner(self.domain_state_account, &crate::ID)?;
+        check_account_key(self.config, &return_config_key().0)?;
         
         check_signer(self.fee_payer).unwrap();
         msg!("fee_payer signature ok");
@@ -111,15 +136,26 @@ pub fn process_increase_price<'a, 'b: 'a>(
     params: Params,
 ) -> ProgramResult {
 
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    raise_bid(_program_id, &accounts, params, None)
+}
+
+/// Outbid the highest bidder, refunding it in the auction's currency
+pub fn raise_bid<'b>(
+    _program_id: &Pubkey,
+    accounts: &Accounts<'_, AccountInfo<'b>>,
+    params: Params,
+    payment: Option<&TokenPayment<'_, 'b>>,
+) -> ProgramResult {
+
     // Check if domain is "dns.kilo"
     if params.name == "dns" && params.root == "kilo" {
         msg!("dns.kilo can't be saled");
         return Err(ProgramError::InvalidArgument);
     }
 
-    let accounts = Accounts::parse(accounts)?;
-    accounts.check()?;
-
     let hashed_root = get_hashed_name(&params.root);
     let (root_domain_key, _) = get_seeds_and_key(
         &web3_domain_name_service::ID,
@@ -139,6 +175,12 @@ pub fn process_increase_price<'a, 'b: 'a>(
         NameStateRecordHeader::unpack_from_slice(&name_state_account.data.borrow())?;
     msg!("unpack state ok");
 
+    let mint = payment.map(|p| *p.mint.key).unwrap_or_default();
+    if name_state_data.mint != mint {
+        msg!("this auction is paid in {}", name_state_data.mint);
+        return Err(ProgramError::InvalidArgument);
+    }
+
     if params.my_price_sol < share_with_cap(name_state_data.highest_price, 1_050_000_000)? {
         msg!("At least 5% markup");
         return Err(ProgramError::InvalidArgument);
@@ -169,6 +211,8 @@ pub fn process_increase_price<'a, 'b: 'a>(
             accounts.fee_payer.clone(),
             accounts.system_program.clone(),
             accounts.referrer_record_account.clone(),
+            accounts.vault.clone(),
+            accounts.config.clone(),
         ];
         if let Some(acc) = accounts.superior_referrer_record {
             account_infos.push(acc.clone());
@@ -192,19 +236,8 @@ pub fn process_increase_price<'a, 'b: 'a>(
     }
 
     let add = math::sub(params.my_price_sol, name_state_data.highest_price)?;
-    
-    invoke(
-        &system_instruction::transfer(
-            accounts.fee_payer.key, &vault_key, add), 
-            &[
-                accounts.fee_payer.clone(),
-                accounts.vault.clone(),
-                accounts.system_program.clone(),
-            ]
-    )?;
-    msg!("transfer to vault add: {:?} sol", add);
 
-    let mut back = name_state_data.highest_price;
+    let mut back = 0;
     let account_data = NameRecordHeader::unpack_from_slice(&accounts.domain_name_account.data.borrow())?;
 
     if account_data.owner == central_state::KEY {
@@ -212,27 +245,101 @@ pub fn process_increase_price<'a, 'b: 'a>(
         back = math::add(back, rent.minimum_balance(NameRecordHeader::LEN + params.name.len() + 4))?;
     }
 
+    if let Some(payment) = payment {
+        transfer_token(
+            payment.token_program,
+            payment.payer_token_account,
+            payment.vault_token_account,
+            accounts.fee_payer,
+            add,
+        )?;
+        msg!("transfer to vault add: {:?} of {}", add, mint);
+
+        // the new bidder repays the last one directly, like a lamport bid
+        refund_token_bid(
+            payment,
+            accounts.fee_payer,
+            accounts.last_bidder,
+            accounts.system_program,
+            name_state_data.highest_price,
+        )?;
+        msg!("transfer back to last_bidder: {:?} of {}", name_state_data.highest_price, mint);
+    } else {
+        invoke(
+            &system_instruction::transfer(
+                accounts.fee_payer.key, &vault_key, add), 
+                &[
+                    accounts.fee_payer.clone(),
+                    accounts.vault.clone(),
+                    accounts.system_program.clone(),
+                ]
+        )?;
+        msg!("transfer to vault add: {:?} sol", add);
+
+        // the new bidder repays the last one directly, the vault now holds the higher bid
+        update_vault_record(accounts.fee_payer, accounts.vault, accounts.system_program, |vault| {
+            vault.owe(add)
+        })?;
+
+        back = math::add(back, name_state_data.highest_price)?;
+    }
+
     // transfer back the deposit
-    invoke(
-        &system_instruction::transfer(
-            accounts.fee_payer.key, accounts.last_bidder.key, back), 
-            &[
-                accounts.fee_payer.clone(),
-                accounts.last_bidder.clone(),
-                accounts.system_program.clone(),
-            ]
+    if back > 0 {
+        invoke(
+            &system_instruction::transfer(
+                accounts.fee_payer.key, accounts.last_bidder.key, back), 
+                &[
+                    accounts.fee_payer.clone(),
+                    accounts.last_bidder.clone(),
+                    accounts.system_program.clone(),
+                ]
+        )?;
+        msg!("transfer all to last_bidder: {:?} sol", back);
+    }
+
+    // a state written by an older version grows to the current layout, the vault pays for it
+    grow_from_vault(
+        accounts.fee_payer,
+        accounts.vault,
+        name_state_account,
+        accounts.system_program,
+        NameStateRecordHeader::LEN,
     )?;
-    msg!("transfer all to last_bidder: {:?} sol", back);
-
-    let new_record = NameStateRecordHeader::new(
-        accounts.fee_payer.key, 
-        get_now_time()?, 
-        params.my_price_sol,
-        &params.root,
-        &params.name,
-    );
+
+    let mut new_record = name_state_data;
+    new_record.place_bid(accounts.fee_payer.key, params.my_price_sol, get_now_time()?)?;
+    let entry = auction_entry(*name_state_account.key, &new_record)?;
+    let auction_page = new_record.auction_page;
+    let bid = BidEntry {
+        bidder: *accounts.fee_payer.key,
+        price: new_record.highest_price,
+        time: new_record.update_time,
+    };
     NameStateRecordHeader::pack(new_record, &mut name_state_account.data.borrow_mut())?;
     msg!("update the name record ok");
 
+    update_indexed_auction(accounts.root_domain.key, accounts.auction_page, auction_page, entry)?;
+
+    record_bid(
+        accounts.bid_history,
+        name_state_account.key,
+        bid,
+    )?;
+
+    let name_state_key = *name_state_account.key;
+    if accounts.last_bidder.key != accounts.fee_payer.key {
+        prune_portfolio(accounts.last_bidder_portfolio, accounts.last_bidder.key, |portfolio| {
+            portfolio.remove_bid(&name_state_key)
+        })?;
+    }
+    update_portfolio(
+        accounts.fee_payer,
+        accounts.system_program,
+        accounts.bidder_portfolio,
+        accounts.fee_payer.key,
+        |portfolio| portfolio.add_bid(name_state_key),
+    )?;
+
     Ok(())
 }
@@ -0,0 +1,87 @@
+//! Raise the bid of an auction paid in an SPL token
+
+use web3_utils::InstructionsAccount;
+use web3_utils::accounts::InstructionsAccount;
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, program_error::ProgramError, pubkey::Pubkey
+};
+
+use crate::{processor::increase_price, utils::token::{TokenPayment, TokenRefund}};
+
+/// Same parameters as `IncreaseBid`, `my_price_sol` is in the token's base units
+pub type Params = increase_price::Params;
+
+#[derive(InstructionsAccount)]
+/// The token accounts, followed by the `IncreaseBid` accounts
+pub struct Accounts<'a, T> {
+    /// The SPL token program
+    pub token_program: &'a T,
+    /// the token the auction is paid in
+    pub mint: &'a T,
+    /// the bidder's token account
+    #[cons(writable)]
+    pub payer_token_account: &'a T,
+    /// the vault's associated token account
+    #[cons(writable)]
+    pub vault_token_account: &'a T,
+    /// the last bidder's associated token account, its bid is paid back there in full
+    #[cons(writable)]
+    pub last_bidder_token_account: &'a T,
+    /// The associated token account program -- to recreate a closed refund account
+    pub associated_token_program: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub const LEN: usize = 6;
+
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            token_program: next_account_info(accounts_iter)?,
+            mint: next_account_info(accounts_iter)?,
+            payer_token_account: next_account_info(accounts_iter)?,
+            vault_token_account: next_account_info(accounts_iter)?,
+            last_bidder_token_account: next_account_info(accounts_iter)?,
+            associated_token_program: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+        self.payment().check()
+    }
+
+    pub fn payment(&self) -> TokenPayment<'a, 'b> {
+        TokenPayment {
+            token_program: self.token_program,
+            mint: self.mint,
+            payer_token_account: self.payer_token_account,
+            vault_token_account: self.vault_token_account,
+            refund: Some(TokenRefund {
+                token_account: self.last_bidder_token_account,
+                associated_token_program: self.associated_token_program,
+            }),
+        }
+    }
+}
+
+pub fn process_increase_price_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    if accounts.len() < Accounts::LEN {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let (token_accounts, bid_accounts) = accounts.split_at(Accounts::LEN);
+
+    let accounts = Accounts::parse(token_accounts)?;
+    accounts.check()?;
+
+    let bid_accounts = increase_price::Accounts::parse(bid_accounts)?;
+    bid_accounts.check()?;
+
+    increase_price::raise_bid(program_id, &bid_accounts, params, Some(&accounts.payment()))
+}
@@ -0,0 +1,84 @@
+//! Let the admins create the protocol statistics account
+
+use web3_utils::{
+    check::{check_account_key, check_signer},
+    InstructionsAccount,
+    accounts::InstructionsAccount,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
+};
+
+use crate::{
+    constants::{ADMIN_ANDY, ADMIN_FANMOCHENG},
+    state::stats::{StatsRecord, get_stats_key},
+    utils::create_pda_account,
+};
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// The administrator account, pays the rent
+    #[cons(writable, signer)]
+    pub administrator: &'a T,
+    /// the statistics PDA
+    #[cons(writable)]
+    pub stats: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            administrator: next_account_info(accounts_iter)?,
+            stats: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+
+        let admin_key = self.administrator.key;
+        if admin_key != &ADMIN_ANDY && admin_key != &ADMIN_FANMOCHENG {
+            msg!("admin error");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        check_signer(self.administrator)?;
+        msg!("administrator signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_init_stats(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let (stats_key, bump) = get_stats_key();
+    check_account_key(accounts.stats, &stats_key)?;
+    if !accounts.stats.data_is_empty() {
+        msg!("stats already exist");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    create_pda_account(
+        accounts.administrator,
+        accounts.stats,
+        accounts.system_program,
+        StatsRecord::LEN,
+        &[b"stats", &[bump]],
+    )?;
+    StatsRecord::default().pack_into_slice(&mut accounts.stats.data.borrow_mut());
+    msg!("create stats ok");
+
+    Ok(())
+}
@@ -12,9 +12,10 @@ use web3_utils::{
 };
 use solana_system_interface::instruction as system_instruction;
 
-use crate::constants::return_vault_key;
-use crate::state::vault::VaultRecord;
+use crate::constants::{return_config_key, return_vault_key};
+use crate::state::config::ConfigRecord;
 use crate::state::{ReferrerRecordHeader, get_referrer_record_key};
+use crate::utils::{if_referrer_valid, resize_pda_account, vault::update_vault_record};
 
 
 #[derive(BorshDeserialize, BorshSerialize, BorshSize)]
@@ -33,6 +34,7 @@ pub fn init_usr (
     let system_account = next_account_info(accounts_iter)?;
     let referrer_record = next_account_info(accounts_iter)?;
     let vault = next_account_info(accounts_iter)?;
+    let config = next_account_info(accounts_iter)?;
     let super_referrer_record = next_account_info(accounts_iter).ok();
     
     // Check that system_account is the system program
@@ -47,19 +49,43 @@ pub fn init_usr (
     let (vault_key, _) = return_vault_key();
     check_account_key(vault, &vault_key)?;
 
+    check_account_key(config, &return_config_key().0)?;
+    let maturity = ConfigRecord::load(config)?.referrer_maturity();
+
     if !referrer_record.data_is_empty() {
         msg!("has registered");
         return Err(ProgramError::InvalidArgument);
     }
 
-    let (vault_key, _) = return_vault_key();
-    if params.referrer_key != vault_key {
+    let mut referrer_key = params.referrer_key;
+    if referrer_key != vault_key {
         msg!("use other's referrer key");
-        let (super_referrer_key, _) = get_referrer_record_key(&params.referrer_key);
+        let (super_referrer_key, _) = get_referrer_record_key(&referrer_key);
         match super_referrer_record {
+            Some(account) if account.data_is_empty() => {
+                check_account_key(account, &super_referrer_key)?;
+                msg!("referrer has left, fall back to the vault");
+                referrer_key = vault_key;
+            }
             Some(account) => {
                 check_account_key(account, &super_referrer_key)?;
-                ReferrerRecordHeader::unpack_from_slice(&account.data.borrow())?;
+                let super_record = ReferrerRecordHeader::unpack_from_slice(&account.data.borrow())?;
+
+                if if_referrer_valid(&super_record, maturity)? {
+                    // a record written by an older version grows to hold the downline stats
+                    resize_pda_account(fee_payer, account, system_account, ReferrerRecordHeader::LEN)?;
+                    let mut data_ref = account.try_borrow_mut_data()?;
+                    let mut super_record = ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
+                    super_record.downline.direct_invitees = super_record.downline.direct_invitees
+                        .checked_add(1)
+                        .ok_or(ProgramError::InvalidArgument)?;
+                    super_record.try_pack_into_slice(&mut data_ref)?;
+                    msg!("add a direct invitee to the referrer");
+                } else {
+                    // too young to refer: sign up under the vault rather than fail
+                    msg!("referrer isn't mature, fall back to the vault");
+                    referrer_key = vault_key;
+                }
             }
             None => {
                 msg!("should got an super referrer");
@@ -106,17 +132,18 @@ pub fn init_usr (
     // Initialize the referrer record data
     let clock = Clock::get()?;
     let referrer_record_data = ReferrerRecordHeader::new(
-        params.referrer_key,
+        referrer_key,
         clock.unix_timestamp,
     );
     
-    referrer_record_data.pack_into_slice(&mut referrer_record.data.borrow_mut());
+    referrer_record_data.try_pack_into_slice(&mut referrer_record.data.borrow_mut())?;
     msg!("Referrer record created successfully");
 
-    let mut vault_record = 
-        VaultRecord::unpack_from_slice(&vault.data.borrow())?;
-    vault_record.usr_count = vault_record.usr_count.checked_add(1)
-        .ok_or(ProgramError::InvalidArgument)?;
+    update_vault_record(fee_payer, vault, system_account, |vault_record| {
+        vault_record.usr_count = vault_record.usr_count.checked_add(1)
+            .ok_or(ProgramError::InvalidArgument)?;
+        Ok(())
+    })?;
     msg!("add a usr count");
 
     Ok(())
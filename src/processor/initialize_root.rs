@@ -20,7 +20,7 @@ use solana_program::{
 };
 use solana_system_interface::instruction as system_instruction;
 use crate::{
-    constants::{return_vault_key}, utils::{ADVANCED_STORAGE, get_hashed_name, is_reserved_root, math}
+    constants::{return_vault_key}, utils::{ADVANCED_STORAGE, get_hashed_name, is_reserved_root, math, vault::update_vault_record}
 };
 
 use crate::state::RootStateRecordHeader;
@@ -156,6 +156,11 @@ pub fn process_initialize_root(
         )?;
         msg!("transfer to vault ok");
 
+        // crowdfunding stays owed until the root is created
+        update_vault_record(accounts.initiator, accounts.vault, accounts.system_program, |vault| {
+            vault.owe(math::sub(ADVANCED_STORAGE, root_state_lamports)?)
+        })?;
+
     }else {
         msg!("root state length err"); 
         return Err(ProgramError::AccountAlreadyInitialized);
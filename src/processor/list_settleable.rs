@@ -0,0 +1,74 @@
+//! Read-only: tell keepers which auctions can be settled now
+
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::set_return_data, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
+};
+
+use web3_domain_name_service::utils::get_seeds_and_key;
+
+use crate::{state::{NameStateRecordHeader, get_name_state_key}, utils::{can_settle, get_hashed_name}};
+
+/// Most name states one call looks at, the return data is capped at 1024 bytes
+pub const MAX_LIST_SETTLEABLE: usize = 31;
+
+/// accounts: name state accounts, e.g. from `getProgramAccounts` filtered by size.
+/// Accounts that aren't auction states are ignored: an account counts only with one of
+/// the name state sizes and at the address its name and root derive. A name longer than
+/// the 32 bytes the state keeps can't be matched and is left out.
+/// return data: a borsh `Vec<Pubkey>` of the ones `FinalizeName` accepts now.
+/// Writes nothing, meant to be simulated.
+pub fn process_list_settleable(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+) -> ProgramResult {
+
+    if accounts.len() > MAX_LIST_SETTLEABLE {
+        msg!("list takes at most {} name states", MAX_LIST_SETTLEABLE);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let mut settleable = Vec::new();
+    for account in accounts {
+        if account.owner != &crate::ID || !is_name_state_len(account.data_len()) {
+            continue;
+        }
+        // registrar accounts of other kinds may share a size, skip what doesn't parse
+        let Ok(name_state) = NameStateRecordHeader::unpack_from_slice(&account.data.borrow()) else {
+            continue;
+        };
+        if !is_name_state_key(account.key, &name_state) {
+            continue;
+        }
+        if can_settle(name_state.end_time)? {
+            settleable.push(*account.key);
+        }
+    }
+    msg!("{} of {} settleable", settleable.len(), accounts.len());
+
+    let data = settleable.try_to_vec().map_err(|_| ProgramError::InvalidArgument)?;
+    set_return_data(&data);
+
+    Ok(())
+}
+
+/// Every layout a name state has been written in
+fn is_name_state_len(len: usize) -> bool {
+    [
+        NameStateRecordHeader::LEGACY_LEN,
+        NameStateRecordHeader::OPENER_LEN,
+        NameStateRecordHeader::UNPAGED_LEN,
+        NameStateRecordHeader::LEN,
+    ].contains(&len)
+}
+
+/// Whether `key` is the name state PDA of the name and root the state records
+fn is_name_state_key(key: &Pubkey, name_state: &NameStateRecordHeader) -> bool {
+    let (root_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&name_state.root()),
+        None,
+        None,
+    );
+    get_name_state_key(&name_state.name(), &root_key).0 == *key
+}
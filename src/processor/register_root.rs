@@ -13,7 +13,7 @@ use solana_program::{
 use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
 
 use crate::{
-    central_state, constants::return_vault_key, cpi::Cpi, state::{ RootStateRecordHeader, reverse_lookup::ReverseLookup, write_data}, utils::{ CREATE_ROOT_TARGET, get_hashed_name, math}
+    central_state, constants::return_vault_key, cpi::Cpi, state::{ RootStateRecordHeader, reverse_lookup::ReverseLookup, write_data}, utils::{ CREATE_ROOT_TARGET, get_hashed_name, math, stats::record_stats, vault::{pay_from_vault, update_vault_record}}
 };
 
 use {
@@ -64,6 +64,9 @@ pub struct Accounts<'a, T> {
     pub root_reverse_lookup: &'a T,
     /// The rent sysvar account
     pub rent_sysvar: &'a T,
+    /// the protocol statistics -- left alone until the admins create them
+    #[cons(writable)]
+    pub stats: &'a T,
 }
 
 impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
@@ -79,6 +82,7 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
             root_name_account: next_account_info(accounts_iter)?,
             root_reverse_lookup: next_account_info(accounts_iter)?,
             rent_sysvar: next_account_info(accounts_iter)?,
+            stats: next_account_info(accounts_iter)?,
         };
 
         check_account_key(accounts.name_service, &web3_domain_name_service::ID)?;
@@ -144,6 +148,8 @@ pub fn process_register_root(
 
     // the lamports that shoudld be return to feepayer
     let mut difference: u64 = 0;
+    // once the root is created: what the vault raised for it and what its accounts cost
+    let mut created: Option<(u64, u64)> = None;
 
     if added_amount >= CREATE_ROOT_TARGET {
         difference = math::sub(added_amount, CREATE_ROOT_TARGET)?;
@@ -202,15 +208,20 @@ pub fn process_register_root(
         }
 
         let lamports = rent.minimum_balance(ReverseLookup { name: params.root_name }.try_to_vec().unwrap().len() + NameRecordHeader::LEN) + root_name_lamports;
-        **accounts.vault.try_borrow_mut_lamports()? -= lamports;
-        **accounts.fee_payer.try_borrow_mut_lamports()? += lamports;
+        pay_from_vault(accounts.vault, accounts.fee_payer, lamports)?;
+
+        // the vault holds the whole target less the root state's rent
+        let raised = math::sub(CREATE_ROOT_TARGET, rent.minimum_balance(RootStateRecordHeader::LEN))?;
+        msg!("root raised {} lamports, {} paid for its accounts", raised, lamports);
+        created = Some((raised, lamports));
     }
 
+    let contribution = math::sub(params.add_lam, difference)?;
     invoke(
     &instruction::transfer(
             accounts.fee_payer.key,
             accounts.vault.key,
-            math::sub(params.add_lam, difference)?,
+            contribution,
         ), 
         &[
             accounts.fee_payer.clone(),
@@ -218,6 +229,23 @@ pub fn process_register_root(
             accounts.system_program.clone(),
         ],
     )?;
-    
+
+    // crowdfunding stays owed until the root is created, then it is the protocol's
+    update_vault_record(accounts.fee_payer, accounts.vault, accounts.system_program, |vault| {
+        vault.owe(contribution)?;
+        if let Some((raised, spent)) = created {
+            vault.settle_owed(raised);
+            vault.earn(math::sub(raised, spent)?)?;
+        }
+        Ok(())
+    })?;
+
+    if created.is_some() {
+        record_stats(accounts.stats, |stats, _| {
+            stats.roots_activated = math::add(stats.roots_activated, 1)?;
+            Ok(())
+        })?;
+    }
+
     Ok(())
 }
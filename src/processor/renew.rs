@@ -0,0 +1,224 @@
+//! Extend the term of a settled domain
+
+use web3_utils::{
+    accounts::InstructionsAccount, 
+    borsh_size::BorshSize, 
+    check::{check_account_key, check_account_owner, check_signer}, 
+    BorshSize, 
+    InstructionsAccount
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, 
+    entrypoint::ProgramResult, 
+    msg, 
+    program::invoke, 
+    program_error::ProgramError, 
+    program_pack::Pack, 
+    pubkey::Pubkey, 
+};
+use solana_system_interface::instruction as system_instruction;
+use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
+
+use crate::{
+    central_state, 
+    constants::{return_config_key, return_vault_key}, 
+    state::{RegistrationRecordHeader, config::ConfigRecord, get_registration_key}, 
+    utils::{MAX_RENEW_YEARS, REGISTRATION_PERIOD, create_pda_account, get_hashed_name, get_now_time, math, next_account_slice, stats::record_fee, transfer_by_chain::{ReferrerChain, transfer_by_referrer_chain}, vault::update_vault_record}
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+/// The required parameters for the `renew` instruction
+pub struct Params {
+    pub domain_name: String,
+    pub years: u8,
+    /// how many accounts `referrer_records` takes, a record and its ledger per level
+    pub referrer_accounts: u8,
+}
+
+#[derive(InstructionsAccount)]
+/// The required accounts for the `renew` instruction
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// The root domain account
+    pub root_domain: &'a T,
+    /// The name account
+    pub name: &'a T,
+    /// the domain's registration record
+    #[cons(writable)]
+    pub registration_record: &'a T,
+    /// the registrar config
+    pub config: &'a T,
+    /// vault
+    #[cons(writable)]
+    pub vault: &'a T,
+    /// the referrer leaderboard -- left alone until the admins create it
+    #[cons(writable)]
+    pub referrer_leaderboard: &'a T,
+    /// the protocol statistics -- left alone until the admins create them
+    #[cons(writable)]
+    pub stats: &'a T,
+    /// whoever pays the renewal -- the fee follows its referrer chain
+    #[cons(writable, signer)]
+    pub fee_payer: &'a T,
+    /// payer's referrer record -- pointed at the vault if its referrer has left
+    #[cons(writable)]
+    pub referrer_record: &'a T,
+    /// the referrers' records, each followed by its ledger, nearest first,
+    /// until the vault or the configured depth
+    #[cons(writable)]
+    pub referrer_records: &'a [T],
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+        referrer_accounts: u8,
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            root_domain: next_account_info(accounts_iter)?,
+            name: next_account_info(accounts_iter)?,
+            registration_record: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+            vault: next_account_info(accounts_iter)?,
+            referrer_leaderboard: next_account_info(accounts_iter)?,
+            stats: next_account_info(accounts_iter)?,
+            fee_payer: next_account_info(accounts_iter)?,
+            referrer_record: next_account_info(accounts_iter)?,
+            referrer_records: next_account_slice(accounts_iter, referrer_accounts)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+        check_account_key(self.config, &return_config_key().0)?;
+        msg!("config id ok");
+        check_account_key(self.vault, &return_vault_key().0)?;
+        msg!("vault ok");
+
+        check_account_owner(self.root_domain, &web3_domain_name_service::ID)?;
+        check_account_owner(self.name, &web3_domain_name_service::ID)?;
+
+        check_signer(self.fee_payer)?;
+        msg!("fee_payer signature ok");
+
+        Ok(())
+    }
+
+    pub fn referrer_chain(&self) -> Result<ReferrerChain<'a, 'b>, ProgramError> {
+        let config = ConfigRecord::load(self.config)?;
+        Ok(ReferrerChain {
+            mint: Pubkey::default(),
+            payer: self.fee_payer,
+            system_program: self.system_program,
+            vault: self.vault,
+            referrer_leaderboard: self.referrer_leaderboard,
+            usr: self.fee_payer,
+            usr_record: self.referrer_record,
+            domain: *self.name.key,
+            referrer_records: self.referrer_records,
+            shares: config.referral_shares().to_vec(),
+            tiers: config.tiers().to_vec(),
+        })
+    }
+}
+
+pub fn process_renew<'a, 'b: 'a>(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts, params.referrer_accounts)?;
+    accounts.check()?;
+
+    if params.years == 0 || params.years > MAX_RENEW_YEARS {
+        msg!("renew between 1 and {} years", MAX_RENEW_YEARS);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (name_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID, 
+        get_hashed_name(&params.domain_name), 
+        None, 
+        Some(accounts.root_domain.key)
+    );
+    check_account_key(accounts.name, &name_key)?;
+    msg!("name account key ok");
+
+    let (registration_key, registration_seeds) = 
+        get_registration_key(&params.domain_name, accounts.root_domain.key);
+    check_account_key(accounts.registration_record, &registration_key)?;
+    msg!("registration record key ok");
+
+    let name_record = NameRecordHeader::unpack_from_slice(&accounts.name.data.borrow())?;
+    if name_record.owner == central_state::KEY {
+        msg!("domain is not settled yet");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let config = ConfigRecord::load(accounts.config)?;
+    let now = get_now_time()?;
+
+    let mut registration = if accounts.registration_record.data_is_empty() {
+        // settled before terms existed, the first renewal starts counting from now
+        msg!("no registration record yet");
+        create_pda_account(
+            accounts.fee_payer, 
+            accounts.registration_record, 
+            accounts.system_program, 
+            RegistrationRecordHeader::LEN, 
+            &registration_seeds.chunks(32).collect::<Vec<&[u8]>>(),
+        )?;
+        RegistrationRecordHeader::new(name_record.owner, now)
+    } else {
+        let registration = 
+            RegistrationRecordHeader::unpack_from_slice(&accounts.registration_record.data.borrow())?;
+        if registration.is_lapsed(config.grace_period, now) {
+            msg!("registration lapsed, the name can only be re-auctioned");
+            return Err(ProgramError::InvalidArgument);
+        }
+        registration
+    };
+
+    let added_time = REGISTRATION_PERIOD
+        .checked_mul(params.years as i64)
+        .ok_or(ProgramError::InvalidArgument)?;
+    registration.owner = name_record.owner;
+    registration.expire_time = registration.expire_time
+        .checked_add(added_time)
+        .ok_or(ProgramError::InvalidArgument)?;
+    registration.pack_into_slice(&mut accounts.registration_record.data.borrow_mut());
+    msg!("renewed until {}", registration.expire_time);
+
+    let fee = math::mul(config.renew_fee_per_year, params.years as u64)?;
+    if fee > 0 {
+        invoke(
+            &system_instruction::transfer(
+                accounts.fee_payer.key, accounts.vault.key, fee), 
+                &[
+                    accounts.fee_payer.clone(),
+                    accounts.vault.clone(),
+                    accounts.system_program.clone(),
+                ]
+        )?;
+        msg!("transfer renew fee to vault: {:?} lamports", fee);
+
+        let credited = transfer_by_referrer_chain(&accounts.referrer_chain()?, fee)?;
+        msg!("add referrer profit and performance ok");
+
+        update_vault_record(accounts.fee_payer, accounts.vault, accounts.system_program, |vault| {
+            vault.owe(credited)?;
+            vault.earn(math::sub(fee, credited)?)
+        })?;
+        record_fee(accounts.stats, fee)?;
+    }
+
+    Ok(())
+}
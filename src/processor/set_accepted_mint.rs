@@ -0,0 +1,158 @@
+//! Let the admins accept an SPL token for bids under a root domain
+
+use web3_utils::{
+    check::{check_account_key, check_account_owner, check_signer},
+    BorshSize,
+    borsh_size::BorshSize,
+    InstructionsAccount,
+    accounts::InstructionsAccount,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program::invoke, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
+};
+use spl_associated_token_account_client::instruction::create_associated_token_account_idempotent;
+use web3_domain_name_service::utils::get_seeds_and_key;
+
+use crate::{
+    constants::{ADMIN_ANDY, ADMIN_FANMOCHENG, return_vault_key}, state::accepted_mint::{AcceptedMintRecord, get_accepted_mint_key}, utils::{create_pda_account, get_hashed_name, token::get_vault_token_key, vault::update_vault_record}
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    pub root_name: String,
+    pub enabled: bool,
+    pub min_price: u64,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// The administrator account
+    #[cons(writable, signer)]
+    pub administrator: &'a T,
+    /// The root domain account
+    pub root_domain: &'a T,
+    /// the accepted token's mint
+    pub mint: &'a T,
+    /// the root's record for this mint
+    #[cons(writable)]
+    pub accepted_mint_record: &'a T,
+    /// vault, keeps a revenue slot for the mint
+    #[cons(writable)]
+    pub vault: &'a T,
+    /// the vault's associated token account -- created when missing
+    #[cons(writable)]
+    pub vault_token_account: &'a T,
+    /// The SPL token program
+    pub token_program: &'a T,
+    /// The associated token account program
+    pub associated_token_program: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            administrator: next_account_info(accounts_iter)?,
+            root_domain: next_account_info(accounts_iter)?,
+            mint: next_account_info(accounts_iter)?,
+            accepted_mint_record: next_account_info(accounts_iter)?,
+            vault: next_account_info(accounts_iter)?,
+            vault_token_account: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            associated_token_program: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        check_account_key(self.token_program, &spl_token::ID)?;
+        check_account_key(self.associated_token_program, &spl_associated_token_account_client::program::ID)?;
+        msg!("program ids ok");
+
+        let admin_key = self.administrator.key;
+        if admin_key != &ADMIN_ANDY && admin_key != &ADMIN_FANMOCHENG {
+            msg!("admin error");
+            return Err(ProgramError::InvalidArgument);
+        }
+        check_signer(self.administrator)?;
+        msg!("admin signature ok");
+
+        check_account_owner(self.root_domain, &web3_domain_name_service::ID)?;
+        check_account_owner(self.mint, &spl_token::ID)?;
+        check_account_key(self.vault, &return_vault_key().0)?;
+        check_account_key(self.vault_token_account, &get_vault_token_key(self.mint.key))?;
+        msg!("vault token account ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_set_accepted_mint(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let (root_domain_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&params.root_name),
+        None,
+        None,
+    );
+    check_account_key(accounts.root_domain, &root_domain_key)?;
+    msg!("root domain ok");
+
+    let (record_key, bump) = get_accepted_mint_key(accounts.root_domain.key, accounts.mint.key);
+    check_account_key(accounts.accepted_mint_record, &record_key)?;
+    msg!("accepted mint record ok");
+
+    if accounts.accepted_mint_record.data_is_empty() {
+        create_pda_account(
+            accounts.administrator,
+            accounts.accepted_mint_record,
+            accounts.system_program,
+            AcceptedMintRecord::LEN,
+            &[b"accepted_mint", accounts.root_domain.key.as_ref(), accounts.mint.key.as_ref(), &[bump]],
+        )?;
+    }
+
+    invoke(
+        &create_associated_token_account_idempotent(
+            accounts.administrator.key,
+            accounts.vault.key,
+            accounts.mint.key,
+            &spl_token::ID,
+        ),
+        &[
+            accounts.administrator.clone(),
+            accounts.vault_token_account.clone(),
+            accounts.vault.clone(),
+            accounts.mint.clone(),
+            accounts.system_program.clone(),
+            accounts.token_program.clone(),
+            accounts.associated_token_program.clone(),
+        ],
+    )?;
+    msg!("vault token account ready");
+
+    // settlements in this token can always book their revenue
+    update_vault_record(accounts.administrator, accounts.vault, accounts.system_program, |vault| {
+        vault.token_slot(accounts.mint.key).map(|_| ())
+    })?;
+
+    let record = AcceptedMintRecord {
+        enabled: params.enabled,
+        min_price: params.min_price,
+    };
+    record.pack_into_slice(&mut accounts.accepted_mint_record.data.borrow_mut());
+    msg!("{} accepts {}: {:?}", params.root_name, accounts.mint.key, record);
+
+    Ok(())
+}
@@ -0,0 +1,241 @@
+use web3_utils::{
+    check::{check_account_key, check_signer},
+    borsh_size::BorshSize,
+    InstructionsAccount,
+    accounts::InstructionsAccount,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program::invoke_signed,
+    rent::Rent,
+    sysvar::Sysvar,
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use solana_system_interface::instruction as system_instruction;
+use crate::{
+    constants::{ADMIN_ANDY, ADMIN_FANMOCHENG, return_config_key}, state::config::{ConfigRecord, MAX_REFERRAL_DEPTH, MAX_TIERS, MAX_TREASURY_RECIPIENTS, TREASURY_BPS, Tier, TreasuryShare}, utils::resize_pda_account
+};
+
+/// 5% -- a resale's seller still gets the bulk of the price
+pub const MAX_SETTLE_TIP_RATE: u64 = 50_000_000;
+
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+/// One config field to set. New fields get new variants at the end,
+/// so the params of older clients keep their meaning as the config grows.
+pub enum ConfigUpdate {
+    RenewFeePerYear(u64),
+    GracePeriod(i64),
+    SettleTipRate(u64),
+    /// shares nearest first, empty for the default table
+    ReferralShares(Vec<u64>),
+    ReferrerChangeCooldown(i64),
+    ReferrerMaturity(i64),
+    /// from the lowest up, empty for none
+    Tiers(Vec<Tier>),
+    /// empty for the default split
+    Treasury(Vec<TreasuryShare>),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+/// Fields left out keep their current value, or the default on a new config
+pub struct Params {
+    pub updates: Vec<ConfigUpdate>,
+}
+
+impl BorshSize for Params {
+    fn borsh_len(&self) -> usize {
+        self.try_to_vec().map(|data| data.len()).unwrap_or(0)
+    }
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// The administrator account
+    #[cons(writable, signer)]
+    pub administrator: &'a T,
+    /// the config PDA
+    #[cons(writable)]
+    pub config: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        let accounts = Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            administrator: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+        };
+
+        check_account_key(accounts.system_program, &solana_program::system_program::ID)?;
+
+        let admin_key = accounts.administrator.key;
+        if admin_key != &ADMIN_ANDY && admin_key != &ADMIN_FANMOCHENG {
+            msg!("admin error");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        check_signer(accounts.administrator)?;
+
+        Ok(accounts)
+    }
+}
+
+pub fn process_set_config(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+    let accounts = Accounts::parse(accounts)?;
+
+    let (config_key, bump) = return_config_key();
+    check_account_key(accounts.config, &config_key)?;
+    msg!("check config ok");
+
+    let mut config = ConfigRecord::load(accounts.config)?;
+    for update in params.updates {
+        apply_update(&mut config, update)?;
+    }
+
+    if config.grace_period < 0 {
+        msg!("grace period can't be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.referrer_change_cooldown < 0 {
+        msg!("referrer change cooldown can't be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.referrer_maturity < 0 {
+        msg!("referrer maturity can't be negative");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.settle_tip_rate > MAX_SETTLE_TIP_RATE {
+        msg!("settle tip can't be over {}", MAX_SETTLE_TIP_RATE);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.referral_depth as usize > MAX_REFERRAL_DEPTH {
+        msg!("referral depth can't be over {}", MAX_REFERRAL_DEPTH);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if config.tier_count as usize > MAX_TIERS {
+        msg!("tier count can't be over {}", MAX_TIERS);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let tiers = config.tiers();
+    if tiers.windows(2).any(|pair| pair[0].threshold >= pair[1].threshold)
+        || tiers.first().is_some_and(|tier| tier.threshold == 0)
+    {
+        msg!("tier thresholds should be above 0 and strictly increasing");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // every level could be held by a record of the best paid tier
+    let max_tier_rate = tiers.iter().map(|tier| tier.rate).max().unwrap_or(0);
+    let shares = config.referral_shares()
+        .iter()
+        .try_fold(0u64, |sum, share| {
+            sum.checked_add(*share)?.checked_add(max_tier_rate)
+        })
+        .ok_or(ProgramError::InvalidArgument)?;
+    if shares > 1_000_000_000 {
+        msg!("referral shares with tier rates add up to more than 100%: {}", shares);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if config.treasury_count as usize > MAX_TREASURY_RECIPIENTS {
+        msg!("treasury can't have over {} recipients", MAX_TREASURY_RECIPIENTS);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let treasury = config.treasury();
+    for (i, share) in treasury.iter().enumerate() {
+        if share.bps == 0 || share.recipient == Pubkey::default() {
+            msg!("treasury recipient {} needs a wallet and a share", i);
+            return Err(ProgramError::InvalidArgument);
+        }
+        if treasury[..i].iter().any(|other| other.recipient == share.recipient) {
+            msg!("treasury recipient listed twice: {}", share.recipient);
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+    let bps = treasury
+        .iter()
+        .try_fold(0u16, |sum, share| sum.checked_add(share.bps))
+        .ok_or(ProgramError::InvalidArgument)?;
+    if bps != TREASURY_BPS {
+        msg!("treasury shares should add up to {} basis points, not {}", TREASURY_BPS, bps);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let rent = Rent::get()?;
+    if accounts.config.data_is_empty() {
+        let config_seeds: &[&[u8]] = &[b"config", &[bump]];
+        invoke_signed(
+            &system_instruction::create_account(
+                accounts.administrator.key, 
+                &config_key, 
+                rent.minimum_balance(ConfigRecord::LEN), 
+                ConfigRecord::LEN as u64, 
+                &crate::ID
+            ), 
+            &[
+                accounts.administrator.clone(),
+                accounts.config.clone(),
+                accounts.system_program.clone(),
+            ], 
+            &[config_seeds]
+        )?;
+        msg!("create config ok");
+    } else if accounts.config.data_len() < ConfigRecord::LEN {
+        // config written by an older version, grow it to the current layout
+        resize_pda_account(
+            accounts.administrator,
+            accounts.config,
+            accounts.system_program,
+            ConfigRecord::LEN,
+        )?;
+        msg!("resize config ok");
+    }
+
+    config.pack_into_slice(&mut accounts.config.data.borrow_mut());
+    msg!("write config ok: {:?}", config);
+
+    Ok(())
+}
+
+fn apply_update(config: &mut ConfigRecord, update: ConfigUpdate) -> ProgramResult {
+    match update {
+        ConfigUpdate::RenewFeePerYear(fee) => config.renew_fee_per_year = fee,
+        ConfigUpdate::GracePeriod(period) => config.grace_period = period,
+        ConfigUpdate::SettleTipRate(rate) => config.settle_tip_rate = rate,
+        ConfigUpdate::ReferralShares(shares) => {
+            config.referral_depth = fill(&mut config.referral_shares, &shares, "referral depth")?;
+        }
+        ConfigUpdate::ReferrerChangeCooldown(cooldown) => config.referrer_change_cooldown = cooldown,
+        ConfigUpdate::ReferrerMaturity(maturity) => config.referrer_maturity = maturity,
+        ConfigUpdate::Tiers(tiers) => {
+            config.tier_count = fill(&mut config.tiers, &tiers, "tier count")?;
+        }
+        ConfigUpdate::Treasury(treasury) => {
+            config.treasury_count = fill(&mut config.treasury, &treasury, "treasury recipients")?;
+        }
+    }
+    Ok(())
+}
+
+/// Put a table at the front of its fixed array, clearing the rest, and return its length
+fn fill<T: Copy + Default>(slots: &mut [T], table: &[T], what: &str) -> Result<u8, ProgramError> {
+    if table.len() > slots.len() {
+        msg!("{} can't be over {}", what, slots.len());
+        return Err(ProgramError::InvalidArgument);
+    }
+    slots.fill(T::default());
+    slots[..table.len()].copy_from_slice(table);
+    Ok(table.len() as u8)
+}
@@ -0,0 +1,118 @@
+//! Let the admins create a leaderboard or change how many entries it keeps
+
+use web3_utils::{
+    check::{check_account_key, check_account_owner, check_signer},
+    BorshSize,
+    borsh_size::BorshSize,
+    InstructionsAccount,
+    accounts::InstructionsAccount,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey, rent::Rent, sysvar::Sysvar
+};
+
+use crate::{
+    constants::{ADMIN_ANDY, ADMIN_FANMOCHENG},
+    state::leaderboard::{Leaderboard, LeaderboardHeader, LeaderboardKind, MAX_LEADERBOARD_LEN, get_leaderboard_key},
+    utils::{create_pda_account, resize_pda_account},
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    pub kind: LeaderboardKind,
+    /// entries to keep, the lowest are dropped when it shrinks
+    pub capacity: u16,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// The administrator account, pays for growing and gets the rent back when shrinking
+    #[cons(writable, signer)]
+    pub administrator: &'a T,
+    /// the leaderboard PDA
+    #[cons(writable)]
+    pub leaderboard: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(accounts: &'a [AccountInfo<'b>]) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            administrator: next_account_info(accounts_iter)?,
+            leaderboard: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+
+        let admin_key = self.administrator.key;
+        if admin_key != &ADMIN_ANDY && admin_key != &ADMIN_FANMOCHENG {
+            msg!("admin error");
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        check_signer(self.administrator)?;
+        msg!("administrator signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_set_leaderboard_size(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    if params.capacity == 0 || params.capacity > MAX_LEADERBOARD_LEN {
+        msg!("a leaderboard keeps 1 to {} entries", MAX_LEADERBOARD_LEN);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let (leaderboard_key, bump) = get_leaderboard_key(params.kind);
+    check_account_key(accounts.leaderboard, &leaderboard_key)?;
+    let space = LeaderboardHeader::space(params.capacity);
+
+    let mut board = if accounts.leaderboard.data_is_empty() {
+        create_pda_account(
+            accounts.administrator,
+            accounts.leaderboard,
+            accounts.system_program,
+            space,
+            &[b"leaderboard", &[params.kind as u8], &[bump]],
+        )?;
+        msg!("create leaderboard ok");
+        Leaderboard::new(params.kind, params.capacity)
+    } else {
+        check_account_owner(accounts.leaderboard, &crate::ID)?;
+        Leaderboard::unpack(&accounts.leaderboard.data.borrow())?
+    };
+
+    board.resize(params.capacity);
+    if space > accounts.leaderboard.data_len() {
+        resize_pda_account(accounts.administrator, accounts.leaderboard, accounts.system_program, space)?;
+    } else if space < accounts.leaderboard.data_len() {
+        accounts.leaderboard.resize(space)?;
+
+        let surplus = accounts.leaderboard.lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(space));
+        **accounts.leaderboard.try_borrow_mut_lamports()? -= surplus;
+        **accounts.administrator.try_borrow_mut_lamports()? += surplus;
+        msg!("return rent: {} to {}", surplus, accounts.administrator.key);
+    }
+
+    board.pack(&mut accounts.leaderboard.data.borrow_mut());
+    msg!("{:?} leaderboard keeps {} entries", params.kind, params.capacity);
+
+    Ok(())
+}
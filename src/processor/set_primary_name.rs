@@ -0,0 +1,136 @@
+//! Let a wallet pick one of its domains as its primary name
+
+use web3_utils::{
+    accounts::InstructionsAccount,
+    borsh_size::BorshSize,
+    check::{check_account_key, check_account_owner, check_signer},
+    BorshSize,
+    InstructionsAccount
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
+
+use crate::{
+    state::primary_name::{PrimaryNameRecord, get_primary_name_key},
+    utils::{create_pda_account, get_hashed_name, resize_pda_account}
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// the domain, without the root
+    pub name: String,
+    pub root: String,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// the wallet picking its primary name, pays the rent
+    #[cons(writable, signer)]
+    pub wallet: &'a T,
+    /// The root domain account
+    pub root_domain: &'a T,
+    /// The domain name account, owned by the wallet
+    pub name: &'a T,
+    /// the wallet's primary name record
+    #[cons(writable)]
+    pub primary_name: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            wallet: next_account_info(accounts_iter)?,
+            root_domain: next_account_info(accounts_iter)?,
+            name: next_account_info(accounts_iter)?,
+            primary_name: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+
+        check_account_owner(self.root_domain, &web3_domain_name_service::ID)?;
+        check_account_owner(self.name, &web3_domain_name_service::ID)?;
+
+        check_signer(self.wallet)?;
+        msg!("wallet signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_set_primary_name(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let (root_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&params.root),
+        None,
+        None
+    );
+    check_account_key(accounts.root_domain, &root_key)?;
+
+    let (name_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&params.name),
+        None,
+        Some(&root_key)
+    );
+    check_account_key(accounts.name, &name_key)?;
+    msg!("name account ok");
+
+    let name_record = NameRecordHeader::unpack_from_slice(&accounts.name.data.borrow())?;
+    if &name_record.owner != accounts.wallet.key {
+        msg!("{}.{} is owned by {}", params.name, params.root, name_record.owner);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let record = PrimaryNameRecord {
+        wallet: *accounts.wallet.key,
+        name_account: name_key,
+        name: params.name,
+        root: params.root,
+    };
+
+    let (primary_name_key, bump) = get_primary_name_key(accounts.wallet.key);
+    check_account_key(accounts.primary_name, &primary_name_key)?;
+    if accounts.primary_name.data_is_empty() {
+        create_pda_account(
+            accounts.wallet,
+            accounts.primary_name,
+            accounts.system_program,
+            record.space(),
+            &[b"primary_name", accounts.wallet.key.as_ref(), &[bump]],
+        )?;
+    } else {
+        check_account_owner(accounts.primary_name, &crate::ID)?;
+        resize_pda_account(accounts.wallet, accounts.primary_name, accounts.system_program, record.space())?;
+    }
+    record.pack(&mut accounts.primary_name.data.borrow_mut());
+    msg!("primary name of {}: {}", accounts.wallet.key, record.domain());
+
+    Ok(())
+}
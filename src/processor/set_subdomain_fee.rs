@@ -0,0 +1,102 @@
+//! Let a domain owner set the fee for subdomains issued under it
+
+use web3_utils::{
+    accounts::InstructionsAccount, 
+    borsh_size::BorshSize, 
+    check::{check_account_key, check_account_owner, check_signer}, 
+    BorshSize, 
+    InstructionsAccount
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, 
+    entrypoint::ProgramResult, 
+    msg, 
+    program_error::ProgramError, 
+    program_pack::Pack, 
+    pubkey::Pubkey, 
+};
+use web3_domain_name_service::state::NameRecordHeader;
+
+use crate::{state::subdomain::{SubdomainFeeRecord, get_subdomain_fee_key}, utils::create_pda_account};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    pub fee: u64,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// the parent domain owner
+    #[cons(writable, signer)]
+    pub parent_owner: &'a T,
+    /// the parent domain name account
+    pub parent_domain: &'a T,
+    /// the parent domain's subdomain fee record
+    #[cons(writable)]
+    pub subdomain_fee_record: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            parent_owner: next_account_info(accounts_iter)?,
+            parent_domain: next_account_info(accounts_iter)?,
+            subdomain_fee_record: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+
+        check_account_owner(self.parent_domain, &web3_domain_name_service::ID)?;
+
+        check_signer(self.parent_owner)?;
+        msg!("parent owner signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_set_subdomain_fee<'a, 'b: 'a>(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let parent_record = NameRecordHeader::unpack_from_slice(&accounts.parent_domain.data.borrow())?;
+    check_account_key(accounts.parent_owner, &parent_record.owner)?;
+    msg!("parent owner ok");
+
+    let (fee_record_key, bump) = get_subdomain_fee_key(accounts.parent_domain.key);
+    check_account_key(accounts.subdomain_fee_record, &fee_record_key)?;
+    msg!("subdomain fee record ok");
+
+    if accounts.subdomain_fee_record.data_is_empty() {
+        create_pda_account(
+            accounts.parent_owner, 
+            accounts.subdomain_fee_record, 
+            accounts.system_program, 
+            SubdomainFeeRecord::LEN, 
+            &[b"subdomain_fee", accounts.parent_domain.key.as_ref(), &[bump]],
+        )?;
+    }
+
+    SubdomainFeeRecord { fee: params.fee }
+        .pack_into_slice(&mut accounts.subdomain_fee_record.data.borrow_mut());
+    msg!("subdomain fee: {} lamports", params.fee);
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Let a usr name a delegate that can withdraw its profit
+
+use web3_utils::{
+    accounts::InstructionsAccount,
+    borsh_size::BorshSize,
+    check::{check_account_key, check_account_owner, check_signer},
+    BorshSize,
+    InstructionsAccount
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+
+use crate::{
+    state::{ReferrerRecordHeader, get_referrer_record_key},
+    utils::resize_pda_account
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// the delegate, default to remove it
+    pub authority: Pubkey,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// the record's owner -- a delegate can't replace itself
+    #[cons(writable, signer)]
+    pub usr: &'a T,
+    /// usr's referrer record
+    #[cons(writable)]
+    pub usr_record: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            usr: next_account_info(accounts_iter)?,
+            usr_record: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+
+        check_account_key(self.usr_record, &get_referrer_record_key(self.usr.key).0)?;
+        check_account_owner(self.usr_record, &crate::ID)?;
+        msg!("usr record ok");
+
+        check_signer(self.usr)?;
+        msg!("usr signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_set_withdraw_authority(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    if &params.authority == accounts.usr.key {
+        msg!("the owner can always withdraw");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // a record written by an older version grows to hold the authority
+    resize_pda_account(
+        accounts.usr,
+        accounts.usr_record,
+        accounts.system_program,
+        ReferrerRecordHeader::LEN,
+    )?;
+
+    let mut data_ref = accounts.usr_record.try_borrow_mut_data()?;
+    let mut record = ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
+    record.withdraw_authority = params.authority;
+    record.try_pack_into_slice(&mut data_ref)?;
+    msg!("withdraw authority: {}", params.authority);
+
+    Ok(())
+}
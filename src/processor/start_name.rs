@@ -13,7 +13,7 @@ use solana_program::{
 use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
 use solana_system_interface::instruction as system_instruction;
 
-use crate::{central_state, constants::return_vault_key, cpi::Cpi, processor::init_usr, state::{NameStateRecordHeader, ReferrerRecordHeader, get_referrer_record_key}, utils::{get_hashed_name, math}
+use crate::{central_state, constants::{return_config_key, return_vault_key}, cpi::Cpi, processor::init_usr, state::{AuctionKind, NameStateRecordHeader, bid_history::BidEntry, ReferrerRecordHeader, RegistrationRecordHeader, config::ConfigRecord, get_referrer_record_key, get_registration_key}, utils::{auction_index::{auction_entry, index_auction}, bid_history::open_bid_history, get_hashed_name, math, portfolio::update_portfolio, stats::record_stats, token::{TokenPayment, transfer_token}, vault::update_vault_record, wrapped::resolve_seller}
 };
 
 
@@ -63,10 +63,43 @@ pub struct Accounts<'a, T> {
     #[cons(writable)]
     pub vault: &'a T,
 
-    /// last owner -- could be default
+    /// last owner -- could be default, the token holder for a wrapped domain
     #[cons(writable)]
     pub last_owner: &'a T,
 
+    /// the domain's registration record -- may be empty
+    #[cons(writable)]
+    pub registration_record: &'a T,
+
+    /// the registrar config
+    pub config: &'a T,
+
+    /// the protocol statistics -- left alone until the admins create them
+    #[cons(writable)]
+    pub stats: &'a T,
+
+    /// the root's auction index -- created with the root's first indexed auction
+    #[cons(writable)]
+    pub auction_index: &'a T,
+
+    /// the index page to list the auction on: one with room, or the next page to open
+    #[cons(writable)]
+    pub auction_page: &'a T,
+
+    /// the bidder's portfolio -- created with its first bid
+    #[cons(writable)]
+    pub portfolio: &'a T,
+
+    /// the domain's wrapped record -- only read for a wrapped domain
+    pub wrapped_name: &'a T,
+
+    /// the last owner's token account for a wrapped domain -- unused otherwise
+    pub last_owner_token_account: &'a T,
+
+    /// the auction's bid history, opened with the opening bid
+    #[cons(writable)]
+    pub bid_history: &'a T,
+
     /// rent sysvar
     pub rent_sysvar: &'a T,
 
@@ -75,6 +108,13 @@ pub struct Accounts<'a, T> {
 }
 
 impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    /// accounts before the optional superior referrer record
+    pub const FIXED_LEN: usize = 21;
+    /// positions of the accounts a batch passes once for all its items: the programs, root,
+    /// central state, the payer with its referrer record and portfolio, vault, config, stats,
+    /// auction index and rent sysvar
+    pub const SHARED: [usize; 12] = [0, 1, 5, 6, 7, 8, 9, 12, 13, 14, 16, 20];
+
     pub fn parse(
         accounts: &'a [AccountInfo<'b>],
     ) -> Result<Self, ProgramError> {
@@ -91,6 +131,15 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
             referrer_record_account: next_account_info(accounts_iter)?,
             vault: next_account_info(accounts_iter)?,
             last_owner: next_account_info(accounts_iter)?,
+            registration_record: next_account_info(accounts_iter)?,
+            config: next_account_info(accounts_iter)?,
+            stats: next_account_info(accounts_iter)?,
+            auction_index: next_account_info(accounts_iter)?,
+            auction_page: next_account_info(accounts_iter)?,
+            portfolio: next_account_info(accounts_iter)?,
+            wrapped_name: next_account_info(accounts_iter)?,
+            last_owner_token_account: next_account_info(accounts_iter)?,
+            bid_history: next_account_info(accounts_iter)?,
             rent_sysvar: next_account_info(accounts_iter)?,
             superior_referrer_record: next_account_info(accounts_iter).ok(),
         })
@@ -104,6 +153,8 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
         msg!("system_program id ok");
         check_account_key(self.central_state, &central_state::KEY)?;
         msg!("central_state id ok");
+        check_account_key(self.config, &return_config_key().0)?;
+        msg!("config id ok");
 
         // when check account owner -> we have two direction:
         // frist: register a initial domain name => 
@@ -125,6 +176,16 @@ impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
 }
 
 
+/// Everything opening an auction needs, read before any account is written
+pub struct Opening {
+    pub name_state_seeds: Vec<u8>,
+    pub name_state_lamports: u64,
+    pub hashed_name: Vec<u8>,
+    pub hashed_reverse_lookup: Vec<u8>,
+    /// the registration of an existing domain whose term lapsed -- the registrar takes it back
+    pub lapsed_registration: Option<RegistrationRecordHeader>,
+}
+
 // trnasfer all
 pub fn process_start_name<'a, 'b: 'a>(
     _program_id: &Pubkey,
@@ -135,7 +196,20 @@ pub fn process_start_name<'a, 'b: 'a>(
     let accounts = Accounts::parse(accounts)?;
     accounts.check()?;
 
-    if params.price_sol < 10_000_000{
+    let opening = prepare_start_name(&accounts, &params, None)?;
+    open_auction(_program_id, &accounts, params, opening, None)
+}
+
+/// Validate an auction start without writing any account,
+/// so a batch can skip an item that would fail here.
+/// A token auction has its lowest price checked against the accepted mint by the caller.
+pub fn prepare_start_name<'b>(
+    accounts: &Accounts<'_, AccountInfo<'b>>,
+    params: &Params,
+    payment: Option<&TokenPayment<'_, 'b>>,
+) -> Result<Opening, ProgramError> {
+
+    if payment.is_none() && params.price_sol < 10_000_000{
         msg!("should larger than 100000000");
         return Err(ProgramError::InvalidArgument);
     }
@@ -164,26 +238,7 @@ pub fn process_start_name<'a, 'b: 'a>(
     check_account_key(referrer_record_account, &referrer_record)?;
     msg!("payer's referrer record account ok");
 
-    if referrer_record_account.data_len() == 0 {
-        msg!("payer's referrer record account need to be intialized");
-        
-        let re_param = init_usr::Params {
-            referrer_key: params.referrer_key
-        };
-        let mut account_infos = vec![
-            accounts.fee_payer.clone(),
-            accounts.system_program.clone(),
-            accounts.referrer_record_account.clone(),
-        ];
-        if let Some(acc) = accounts.superior_referrer_record {
-            account_infos.push(acc.clone());
-        }
-        init_usr::init_usr(_program_id, 
-            &account_infos, 
-            re_param
-        )?;
-
-    }else {
+    if referrer_record_account.data_len() != 0 {
         let referrer_data = 
             ReferrerRecordHeader::unpack_from_slice(&referrer_record_account.data.borrow())?;
         if referrer_data.referrer_account != params.referrer_key {
@@ -191,6 +246,22 @@ pub fn process_start_name<'a, 'b: 'a>(
             return Err(ProgramError::InvalidArgument);
         }
     }
+    // the payer signs up on the way, its referrer's record must be there to check against
+    else if params.referrer_key != vault_key {
+        let (superior_key, _) = get_referrer_record_key(&params.referrer_key);
+        match accounts.superior_referrer_record {
+            Some(account) => {
+                check_account_key(account, &superior_key)?;
+                if !account.data_is_empty() {
+                    ReferrerRecordHeader::unpack_from_slice(&account.data.borrow())?;
+                }
+            }
+            None => {
+                msg!("should got an super referrer");
+                return Err(ProgramError::InvalidArgument);
+            }
+        }
+    }
     
     let (root_account_key, _) = get_seeds_and_key(
         accounts.naming_service_program.key, 
@@ -236,12 +307,99 @@ pub fn process_start_name<'a, 'b: 'a>(
         return Err(ProgramError::InvalidArgument);
     }
 
+    // the opener pays the state's rent on top of the price and gets it back at settlement
     let name_state_lamports = rent.minimum_balance(NameStateRecordHeader::LEN);
-    if name_state_lamports > params.price_sol {
-        msg!("this can't be happend");
-        return Err(ProgramError::InvalidArgument);
+
+    let mut lapsed_registration = None;
+    if !accounts.domain_name_account.data_is_empty(){
+        msg!("domain exsist");
+        let domain_record = NameRecordHeader::unpack_from_slice(&accounts.domain_name_account.data.borrow())?;
+
+        let (registration_key, _) = get_registration_key(&params.name, accounts.root_domain.key);
+        check_account_key(accounts.registration_record, &registration_key)?;
+
+        if !accounts.registration_record.data_is_empty() {
+            let config = ConfigRecord::load(accounts.config)?;
+            let registration = 
+                RegistrationRecordHeader::unpack_from_slice(&accounts.registration_record.data.borrow())?;
+            if registration.is_lapsed(config.grace_period, Clock::get()?.unix_timestamp) {
+                msg!("registration lapsed at {}", registration.expire_time);
+                lapsed_registration = Some(registration);
+            }
+        }
+
+        // a lapsed wrapped name is reclaimed from the registrar PDA, even if its token was burnt
+        let seller = if lapsed_registration.is_some() {
+            domain_record.owner
+        } else {
+            resolve_seller(&name_account_key, &domain_record.owner, accounts.wrapped_name, accounts.last_owner_token_account)?
+        };
+        check_account_key(accounts.last_owner, &seller)?;
+
+        // the owner's custom price is in lamports, only a lapsed name can be re-auctioned in tokens
+        if lapsed_registration.is_none() && payment.is_some() {
+            msg!("a resale is paid in lamports");
+            return Err(ProgramError::InvalidArgument);
+        }
+        if lapsed_registration.is_none() && domain_record.custom_price != params.price_sol {
+            msg!("should be same as owner's custom price, custom: {}, you: {}", domain_record.custom_price, params.price_sol);
+            return Err(ProgramError::InvalidArgument);
+        }
     }
 
+    Ok(Opening {
+        name_state_seeds,
+        name_state_lamports,
+        hashed_name,
+        hashed_reverse_lookup,
+        lapsed_registration,
+    })
+}
+
+/// Take the opening bid, write the auction state and create or lock the domain
+pub fn open_auction<'b>(
+    program_id: &Pubkey,
+    accounts: &Accounts<'_, AccountInfo<'b>>,
+    params: Params,
+    opening: Opening,
+    payment: Option<&TokenPayment<'_, 'b>>,
+) -> ProgramResult {
+
+    let Opening {
+        name_state_seeds,
+        name_state_lamports,
+        hashed_name,
+        hashed_reverse_lookup,
+        lapsed_registration,
+    } = opening;
+
+    let rent = Rent::get()?;
+
+    if accounts.referrer_record_account.data_len() == 0 {
+        msg!("payer's referrer record account need to be intialized");
+        
+        let re_param = init_usr::Params {
+            referrer_key: params.referrer_key
+        };
+        let mut account_infos = vec![
+            accounts.fee_payer.clone(),
+            accounts.system_program.clone(),
+            accounts.referrer_record_account.clone(),
+            accounts.vault.clone(),
+            accounts.config.clone(),
+        ];
+        if let Some(acc) = accounts.superior_referrer_record {
+            account_infos.push(acc.clone());
+        }
+        init_usr::init_usr(program_id, 
+            &account_infos, 
+            re_param
+        )?;
+    }
+
+    let name_state_account = accounts.domain_state_account;
+    let name_state_key = *name_state_account.key;
+
     invoke(
     &system_instruction::transfer(
         accounts.fee_payer.key, &name_state_key, name_state_lamports), 
@@ -267,37 +425,67 @@ pub fn process_start_name<'a, 'b: 'a>(
         &[&name_state_seeds.chunks(32).collect::<Vec<&[u8]>>()],
     )?;
 
-    let name_state_record = NameStateRecordHeader::new(
+    // a lapsed name settles like a new one
+    let kind = if !accounts.domain_name_account.data_is_empty() && lapsed_registration.is_none() {
+        AuctionKind::Resale
+    } else {
+        AuctionKind::Registration
+    };
+    let mut name_state_record = NameStateRecordHeader::new(
         accounts.fee_payer.key, 
         Clock::get()?.unix_timestamp, 
         params.price_sol,
         &params.root_name,
         &params.name,
+        &payment.map(|p| *p.mint.key).unwrap_or_default(),
+        kind,
     );
+    let page = index_auction(
+        accounts.fee_payer,
+        accounts.system_program,
+        accounts.root_domain.key,
+        accounts.auction_index,
+        accounts.auction_page,
+        auction_entry(name_state_key, &name_state_record)?,
+    )?;
+    name_state_record.auction_page = Some(page);
+    msg!("auction indexed on page {}", page);
+
     name_state_record.pack_into_slice(& mut name_state_account.data.borrow_mut());
     msg!("write name state ok: {}.{}", params.name, params.root_name);
 
+    open_bid_history(
+        accounts.fee_payer,
+        accounts.system_program,
+        accounts.bid_history,
+        &name_state_key,
+        BidEntry {
+            bidder: *accounts.fee_payer.key,
+            price: params.price_sol,
+            time: name_state_record.start_time,
+        },
+    )?;
+
+    update_portfolio(
+        accounts.fee_payer,
+        accounts.system_program,
+        accounts.portfolio,
+        accounts.fee_payer.key,
+        |portfolio| portfolio.add_bid(name_state_key),
+    )?;
+
     if !accounts.domain_name_account.data_is_empty(){
         msg!("domain exsist");
-        let domain_record = NameRecordHeader::unpack_from_slice(&accounts.domain_name_account.data.borrow())?;
-        check_account_key(accounts.last_owner, &domain_record.owner)?;
 
-        if domain_record.custom_price != params.price_sol {
-            msg!("should be same as owner's custom price, custom: {}, you: {}", domain_record.custom_price, params.price_sol);
-            return Err(ProgramError::InvalidArgument);
+        if let Some(mut registration) = lapsed_registration {
+            // the lapsed owner loses the name, the auction result settles like a new registration
+            msg!("registration lapsed, reclaimed by registrar");
+            registration.owner = Pubkey::default();
+            registration.pack_into_slice(&mut accounts.registration_record.data.borrow_mut());
         }
 
         // directly transfer to vault, when the domain has settled, add profit to owner's profit
-        invoke(
-            &system_instruction::transfer(
-                accounts.fee_payer.key, accounts.vault.key, math::sub(params.price_sol, name_state_lamports)?), 
-                &[
-                    accounts.fee_payer.clone(),
-                    accounts.vault.clone(),
-                    accounts.system_program.clone(),
-                ]
-        )?;
-        msg!("transfer to vault: {:?} sol", math::sub(params.price_sol, name_state_lamports)?);
+        take_opening_bid(accounts, params.price_sol, payment)?;
         
         let central_state_signer_seeds: &[&[u8]] = &[&crate::ID.to_bytes(), &[central_state::NONCE]];
         Cpi::change_preview(
@@ -311,16 +499,7 @@ pub fn process_start_name<'a, 'b: 'a>(
         )?;
     }else {
 
-        invoke(
-            &system_instruction::transfer(
-                accounts.fee_payer.key, &vault_key, math::sub(params.price_sol, name_state_lamports)?), 
-                &[
-                    accounts.fee_payer.clone(),
-                    accounts.vault.clone(),
-                    accounts.system_program.clone(),
-                ]
-        )?;
-        msg!("transfer all to vault: {:?} sol", math::sub(params.price_sol, name_state_lamports)?);
+        take_opening_bid(accounts, params.price_sol, payment)?;
 
         let central_state_signer_seeds: &[&[u8]] = &[&crate::ID.to_bytes(), &[central_state::NONCE]];
         Cpi::create_name_account(
@@ -355,5 +534,46 @@ pub fn process_start_name<'a, 'b: 'a>(
         }
     }
 
-    Ok(())
+    record_stats(accounts.stats, |stats, now| {
+        stats.active_auctions = math::add(stats.active_auctions, 1)?;
+        let today = stats.today(now);
+        today.auctions_opened = math::add(today.auctions_opened, 1)?;
+        Ok(())
+    })
+}
+
+/// Move the opening bid into the vault, in lamports or in the accepted token
+fn take_opening_bid<'b>(
+    accounts: &Accounts<'_, AccountInfo<'b>>,
+    price: u64,
+    payment: Option<&TokenPayment<'_, 'b>>,
+) -> ProgramResult {
+
+    if let Some(payment) = payment {
+        transfer_token(
+            payment.token_program,
+            payment.payer_token_account,
+            payment.vault_token_account,
+            accounts.fee_payer,
+            price,
+        )?;
+        msg!("transfer to vault: {:?} of {}", price, payment.mint.key);
+        return Ok(());
+    }
+
+    invoke(
+        &system_instruction::transfer(
+            accounts.fee_payer.key, accounts.vault.key, price), 
+            &[
+                accounts.fee_payer.clone(),
+                accounts.vault.clone(),
+                accounts.system_program.clone(),
+            ]
+    )?;
+    msg!("transfer to vault: {:?} sol", price);
+
+    // held for the bidder until the auction settles
+    update_vault_record(accounts.fee_payer, accounts.vault, accounts.system_program, |vault| {
+        vault.owe(price)
+    })
 }
@@ -0,0 +1,103 @@
+//! Begin a name auction paid in an SPL token the root accepts
+
+use web3_utils::{
+    check::{check_account_key, check_account_owner},
+    InstructionsAccount,
+    accounts::InstructionsAccount,
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo}, entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
+};
+
+use crate::{
+    processor::start_name, state::accepted_mint::{AcceptedMintRecord, get_accepted_mint_key}, utils::token::TokenPayment
+};
+
+/// Same parameters as `BeginNameRegistration`, `price_sol` is in the token's base units
+pub type Params = start_name::Params;
+
+#[derive(InstructionsAccount)]
+/// The token accounts, followed by the `BeginNameRegistration` accounts
+pub struct Accounts<'a, T> {
+    /// The SPL token program
+    pub token_program: &'a T,
+    /// the token the bid is paid in
+    pub mint: &'a T,
+    /// the root's record for this mint
+    pub accepted_mint_record: &'a T,
+    /// the bidder's token account
+    #[cons(writable)]
+    pub payer_token_account: &'a T,
+    /// the vault's associated token account
+    #[cons(writable)]
+    pub vault_token_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub const LEN: usize = 5;
+
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            token_program: next_account_info(accounts_iter)?,
+            mint: next_account_info(accounts_iter)?,
+            accepted_mint_record: next_account_info(accounts_iter)?,
+            payer_token_account: next_account_info(accounts_iter)?,
+            vault_token_account: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_owner(self.accepted_mint_record, &crate::ID)?;
+        self.payment().check()
+    }
+
+    pub fn payment(&self) -> TokenPayment<'a, 'b> {
+        TokenPayment {
+            token_program: self.token_program,
+            mint: self.mint,
+            payer_token_account: self.payer_token_account,
+            vault_token_account: self.vault_token_account,
+            refund: None,
+        }
+    }
+}
+
+pub fn process_start_name_token(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    if accounts.len() < Accounts::LEN {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let (token_accounts, name_accounts) = accounts.split_at(Accounts::LEN);
+
+    let accounts = Accounts::parse(token_accounts)?;
+    accounts.check()?;
+
+    let name_accounts = start_name::Accounts::parse(name_accounts)?;
+    name_accounts.check()?;
+
+    let (accepted_mint_key, _) = get_accepted_mint_key(name_accounts.root_domain.key, accounts.mint.key);
+    check_account_key(accounts.accepted_mint_record, &accepted_mint_key)?;
+    let accepted_mint =
+        AcceptedMintRecord::unpack_from_slice(&accounts.accepted_mint_record.data.borrow())?;
+
+    if !accepted_mint.enabled {
+        msg!("{} isn't accepted under {}", accounts.mint.key, params.root_name);
+        return Err(ProgramError::InvalidArgument);
+    }
+    if params.price_sol < accepted_mint.min_price {
+        msg!("should larger than {}", accepted_mint.min_price);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let payment = accounts.payment();
+    let opening = start_name::prepare_start_name(&name_accounts, &params, Some(&payment))?;
+    start_name::open_auction(program_id, &name_accounts, params, opening, Some(&payment))
+}
@@ -0,0 +1,212 @@
+//! Burn a wrapped domain's token and hand the domain back to its holder
+
+use web3_utils::{
+    accounts::InstructionsAccount,
+    borsh_size::BorshSize,
+    check::{check_account_key, check_account_owner, check_signer},
+    BorshSize,
+    InstructionsAccount
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use spl_associated_token_account_client::address::get_associated_token_address;
+use spl_token::state::Account as TokenAccount;
+use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
+
+use crate::{
+    central_state,
+    cpi::Cpi,
+    state::{get_name_state_key, wrapped::{WrappedNameRecord, get_wrapped_name_key}},
+    utils::get_hashed_name
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// the domain, without the root
+    pub name: String,
+    pub root: String,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The naming service program ID
+    pub naming_service_program: &'a T,
+    /// The SPL token program
+    pub token_program: &'a T,
+    /// The central state account
+    pub central_state: &'a T,
+    /// the token holder, receives the domain and the rent
+    #[cons(writable, signer)]
+    pub holder: &'a T,
+    /// The root domain account
+    pub root_domain: &'a T,
+    /// The domain name account
+    #[cons(writable)]
+    pub name: &'a T,
+    /// The domain auction state account -- a domain being auctioned can't be unwrapped
+    pub domain_state_account: &'a T,
+    /// the registrar PDA holding the domain, closed
+    #[cons(writable)]
+    pub wrapped_name: &'a T,
+    /// the domain's token
+    #[cons(writable)]
+    pub mint: &'a T,
+    /// the holder's token account, closed once the token is burnt
+    #[cons(writable)]
+    pub holder_token_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            naming_service_program: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            central_state: next_account_info(accounts_iter)?,
+            holder: next_account_info(accounts_iter)?,
+            root_domain: next_account_info(accounts_iter)?,
+            name: next_account_info(accounts_iter)?,
+            domain_state_account: next_account_info(accounts_iter)?,
+            wrapped_name: next_account_info(accounts_iter)?,
+            mint: next_account_info(accounts_iter)?,
+            holder_token_account: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.naming_service_program, &web3_domain_name_service::ID)?;
+        msg!("nameservice id ok");
+        check_account_key(self.token_program, &spl_token::ID)?;
+        msg!("token program id ok");
+        check_account_key(self.central_state, &central_state::KEY)?;
+        msg!("central_state id ok");
+
+        check_account_owner(self.root_domain, &web3_domain_name_service::ID)?;
+        check_account_owner(self.name, &web3_domain_name_service::ID)?;
+        check_account_owner(self.wrapped_name, &crate::ID)?;
+        check_account_owner(self.holder_token_account, &spl_token::ID)?;
+
+        check_signer(self.holder)?;
+        msg!("holder signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_unwrap(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let (root_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&params.root),
+        None,
+        None
+    );
+    check_account_key(accounts.root_domain, &root_key)?;
+
+    let (name_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&params.name),
+        None,
+        Some(&root_key)
+    );
+    check_account_key(accounts.name, &name_key)?;
+    msg!("name account ok");
+
+    let (wrapped_name_key, _) = get_wrapped_name_key(&name_key);
+    check_account_key(accounts.wrapped_name, &wrapped_name_key)?;
+
+    // a record whose domain was sold at auction no longer holds it
+    let name_record = NameRecordHeader::unpack_from_slice(&accounts.name.data.borrow())?;
+    if name_record.owner != wrapped_name_key {
+        msg!("{}.{} is not wrapped", params.name, params.root);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    check_account_key(accounts.domain_state_account, &get_name_state_key(&params.name, &root_key).0)?;
+    if !accounts.domain_state_account.data_is_empty() {
+        msg!("domain is being auctioned");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let record = WrappedNameRecord::unpack_from_slice(&accounts.wrapped_name.data.borrow())?;
+    check_account_key(accounts.mint, &record.mint)?;
+    let holder = record.holder(&accounts.holder_token_account.data.borrow())?;
+    check_account_key(accounts.holder, &holder)?;
+    msg!("token holder ok");
+
+    // only the holder's own associated account is closed, and only when the holder may close it
+    let token_account = TokenAccount::unpack(&accounts.holder_token_account.data.borrow())?;
+    let close_token_account = accounts.holder_token_account.key
+        == &get_associated_token_address(&holder, &record.mint)
+        && token_account.close_authority.unwrap_or(holder) == holder;
+
+    invoke(
+        &spl_token::instruction::burn(
+            accounts.token_program.key, accounts.holder_token_account.key, accounts.mint.key, accounts.holder.key, &[], 1
+        )?,
+        &[
+            accounts.holder_token_account.clone(),
+            accounts.mint.clone(),
+            accounts.holder.clone(),
+            accounts.token_program.clone(),
+        ],
+    )?;
+    if close_token_account {
+        invoke(
+            &spl_token::instruction::close_account(
+                accounts.token_program.key, accounts.holder_token_account.key, accounts.holder.key, accounts.holder.key, &[]
+            )?,
+            &[
+                accounts.holder_token_account.clone(),
+                accounts.holder.clone(),
+                accounts.token_program.clone(),
+            ],
+        )?;
+    }
+    msg!("burnt {}", record.mint);
+
+    let central_state_signer_seeds: &[&[u8]] = &[&crate::ID.to_bytes(), &[central_state::NONCE]];
+    Cpi::transfer_name_account(
+        accounts.naming_service_program,
+        accounts.central_state,
+        accounts.name,
+        accounts.root_domain,
+        accounts.holder.key,
+        central_state_signer_seeds,
+        Some(name_record.custom_price),
+    )?;
+
+    {
+        let lamports = **accounts.wrapped_name.lamports.borrow();
+        **accounts.wrapped_name.try_borrow_mut_lamports()? -= lamports;
+        **accounts.holder.try_borrow_mut_lamports()? += lamports;
+
+        let mut data = accounts.wrapped_name.try_borrow_mut_data()?;
+        for byte in data.iter_mut() {
+            *byte = 0;
+        }
+        accounts.wrapped_name.assign(&solana_program::system_program::ID);
+    }
+    msg!("{}.{} unwrapped to {}", params.name, params.root, accounts.holder.key);
+
+    Ok(())
+}
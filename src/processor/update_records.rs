@@ -0,0 +1,160 @@
+//! Let a domain's owner set the records the domain resolves to
+
+use web3_utils::{
+    accounts::InstructionsAccount,
+    borsh_size::BorshSize,
+    check::{check_account_key, check_account_owner, check_signer},
+    InstructionsAccount
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
+
+use crate::{
+    state::domain_records::{DomainRecordsAccount, RecordUpdate, get_domain_records_key},
+    utils::{create_pda_account, get_hashed_name, resize_pda_account, shrink_pda_account}
+};
+
+#[derive(BorshDeserialize, BorshSerialize, Debug)]
+pub struct Params {
+    /// the domain, without the root
+    pub name: String,
+    pub root: String,
+    /// applied in order
+    pub updates: Vec<RecordUpdate>,
+}
+
+// records differ in length, the serialized size is the only honest one
+impl BorshSize for Params {
+    fn borsh_len(&self) -> usize {
+        self.try_to_vec().map(|data| data.len()).unwrap_or(0)
+    }
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The system program account
+    pub system_program: &'a T,
+    /// the domain's owner, pays the rent
+    #[cons(writable, signer)]
+    pub owner: &'a T,
+    /// The root domain account
+    pub root_domain: &'a T,
+    /// The domain name account
+    pub name: &'a T,
+    /// the domain's records
+    #[cons(writable)]
+    pub domain_records: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            system_program: next_account_info(accounts_iter)?,
+            owner: next_account_info(accounts_iter)?,
+            root_domain: next_account_info(accounts_iter)?,
+            name: next_account_info(accounts_iter)?,
+            domain_records: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+
+        check_account_owner(self.root_domain, &web3_domain_name_service::ID)?;
+        check_account_owner(self.name, &web3_domain_name_service::ID)?;
+
+        check_signer(self.owner)?;
+        msg!("owner signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_update_records(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let (root_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&params.root),
+        None,
+        None
+    );
+    check_account_key(accounts.root_domain, &root_key)?;
+
+    let (name_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&params.name),
+        None,
+        Some(&root_key)
+    );
+    check_account_key(accounts.name, &name_key)?;
+
+    let name_record = NameRecordHeader::unpack_from_slice(&accounts.name.data.borrow())?;
+    if &name_record.owner != accounts.owner.key {
+        msg!("{}.{} is owned by {}", params.name, params.root, name_record.owner);
+        return Err(ProgramError::InvalidArgument);
+    }
+    msg!("domain owner ok");
+
+    let (records_key, bump) = get_domain_records_key(&name_key);
+    check_account_key(accounts.domain_records, &records_key)?;
+
+    let exists = !accounts.domain_records.data_is_empty();
+    let mut records = if exists {
+        check_account_owner(accounts.domain_records, &crate::ID)?;
+        DomainRecordsAccount::unpack(&accounts.domain_records.data.borrow())?
+    } else {
+        DomainRecordsAccount::default()
+    };
+
+    // a new owner starts from nothing, not from the records of the one before
+    if records.owner != name_record.owner {
+        records = DomainRecordsAccount {
+            domain: name_key,
+            owner: name_record.owner,
+            records: Vec::new(),
+        };
+    }
+    for update in params.updates {
+        records.apply(update)?;
+    }
+
+    let space = records.space();
+    if exists {
+        // the owner pays to grow the account and gets the rent back when it shrinks
+        resize_pda_account(accounts.owner, accounts.domain_records, accounts.system_program, space)?;
+        shrink_pda_account(accounts.domain_records, accounts.owner, space)?;
+    } else {
+        create_pda_account(
+            accounts.owner,
+            accounts.domain_records,
+            accounts.system_program,
+            space,
+            &[b"domain_records", name_key.as_ref(), &[bump]],
+        )?;
+    }
+    records.pack(&mut accounts.domain_records.data.borrow_mut());
+    msg!("{}.{} holds {} records", params.name, params.root, records.records.len());
+
+    Ok(())
+}
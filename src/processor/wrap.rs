@@ -0,0 +1,267 @@
+//! Wrap a domain into a supply-1 SPL token the owner can trade anywhere
+
+use web3_utils::{
+    accounts::InstructionsAccount,
+    borsh_size::BorshSize,
+    check::{check_account_key, check_account_owner, check_signer},
+    BorshSize,
+    InstructionsAccount
+};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    entrypoint::ProgramResult,
+    msg,
+    program::invoke,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    sysvar::Sysvar,
+};
+use solana_system_interface::instruction as system_instruction;
+use spl_associated_token_account_client::{
+    address::get_associated_token_address, instruction::create_associated_token_account_idempotent
+};
+use spl_token::{instruction::AuthorityType, state::Mint};
+use web3_domain_name_service::{state::NameRecordHeader, utils::get_seeds_and_key};
+
+use crate::{
+    central_state,
+    cpi::Cpi,
+    state::{get_name_state_key, wrapped::{WrappedNameRecord, get_wrapped_name_key}},
+    utils::{create_pda_account, get_hashed_name, get_now_time}
+};
+
+#[derive(BorshDeserialize, BorshSerialize, BorshSize, Debug)]
+pub struct Params {
+    /// the domain, without the root
+    pub name: String,
+    pub root: String,
+}
+
+#[derive(InstructionsAccount)]
+pub struct Accounts<'a, T> {
+    /// The naming service program ID
+    pub naming_service_program: &'a T,
+    /// The system program account
+    pub system_program: &'a T,
+    /// The SPL token program
+    pub token_program: &'a T,
+    /// The associated token account program
+    pub associated_token_program: &'a T,
+    /// The central state account
+    pub central_state: &'a T,
+    /// the domain owner, pays the rent and receives the token
+    #[cons(writable, signer)]
+    pub owner: &'a T,
+    /// The root domain account
+    pub root_domain: &'a T,
+    /// The domain name account
+    #[cons(writable)]
+    pub name: &'a T,
+    /// The domain auction state account -- a domain being auctioned can't be wrapped
+    pub domain_state_account: &'a T,
+    /// the registrar PDA the domain moves to
+    #[cons(writable)]
+    pub wrapped_name: &'a T,
+    /// a new keypair for the token
+    #[cons(writable, signer)]
+    pub mint: &'a T,
+    /// the owner's associated token account for the token
+    #[cons(writable)]
+    pub owner_token_account: &'a T,
+}
+
+impl<'a, 'b: 'a> Accounts<'a, AccountInfo<'b>> {
+    pub fn parse(
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<Self, ProgramError> {
+        let accounts_iter = &mut accounts.iter();
+        Ok(Accounts {
+            naming_service_program: next_account_info(accounts_iter)?,
+            system_program: next_account_info(accounts_iter)?,
+            token_program: next_account_info(accounts_iter)?,
+            associated_token_program: next_account_info(accounts_iter)?,
+            central_state: next_account_info(accounts_iter)?,
+            owner: next_account_info(accounts_iter)?,
+            root_domain: next_account_info(accounts_iter)?,
+            name: next_account_info(accounts_iter)?,
+            domain_state_account: next_account_info(accounts_iter)?,
+            wrapped_name: next_account_info(accounts_iter)?,
+            mint: next_account_info(accounts_iter)?,
+            owner_token_account: next_account_info(accounts_iter)?,
+        })
+    }
+
+    pub fn check(&self) -> Result<(), ProgramError> {
+
+        check_account_key(self.naming_service_program, &web3_domain_name_service::ID)?;
+        msg!("nameservice id ok");
+        check_account_key(self.system_program, &solana_program::system_program::ID)?;
+        msg!("system_program id ok");
+        check_account_key(self.token_program, &spl_token::ID)?;
+        msg!("token program id ok");
+        check_account_key(self.associated_token_program, &spl_associated_token_account_client::program::ID)?;
+        msg!("associated token program id ok");
+        check_account_key(self.central_state, &central_state::KEY)?;
+        msg!("central_state id ok");
+
+        check_account_owner(self.root_domain, &web3_domain_name_service::ID)?;
+        check_account_owner(self.name, &web3_domain_name_service::ID)?;
+
+        check_signer(self.owner)?;
+        check_signer(self.mint)?;
+        msg!("owner and mint signature ok");
+
+        Ok(())
+    }
+}
+
+pub fn process_wrap(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    params: Params,
+) -> ProgramResult {
+
+    let accounts = Accounts::parse(accounts)?;
+    accounts.check()?;
+
+    let (root_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&params.root),
+        None,
+        None
+    );
+    check_account_key(accounts.root_domain, &root_key)?;
+
+    let (name_key, _) = get_seeds_and_key(
+        &web3_domain_name_service::ID,
+        get_hashed_name(&params.name),
+        None,
+        Some(&root_key)
+    );
+    check_account_key(accounts.name, &name_key)?;
+    msg!("name account ok");
+
+    let name_record = NameRecordHeader::unpack_from_slice(&accounts.name.data.borrow())?;
+    if &name_record.owner != accounts.owner.key {
+        msg!("{}.{} is owned by {}", params.name, params.root, name_record.owner);
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // the name service clears the bidder's preview on transfer
+    check_account_key(accounts.domain_state_account, &get_name_state_key(&params.name, &root_key).0)?;
+    if !accounts.domain_state_account.data_is_empty() {
+        msg!("domain is being auctioned");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if !accounts.mint.data_is_empty() {
+        msg!("mint already in use");
+        return Err(ProgramError::InvalidArgument);
+    }
+    check_account_key(
+        accounts.owner_token_account,
+        &get_associated_token_address(accounts.owner.key, accounts.mint.key),
+    )?;
+    msg!("owner token account ok");
+
+    mint_domain_token(&accounts)?;
+    msg!("minted {} to {}", accounts.mint.key, accounts.owner.key);
+
+    let (wrapped_name_key, bump) = get_wrapped_name_key(&name_key);
+    check_account_key(accounts.wrapped_name, &wrapped_name_key)?;
+    if accounts.wrapped_name.data_is_empty() {
+        create_pda_account(
+            accounts.owner,
+            accounts.wrapped_name,
+            accounts.system_program,
+            WrappedNameRecord::LEN,
+            &[b"wrapped_name", name_key.as_ref(), &[bump]],
+        )?;
+    } else {
+        // left behind by a domain wrapped before settlements closed the record
+        check_account_owner(accounts.wrapped_name, &crate::ID)?;
+    }
+    WrappedNameRecord {
+        name_account: name_key,
+        mint: *accounts.mint.key,
+        wrapped_at: get_now_time()?,
+    }
+    .pack_into_slice(&mut accounts.wrapped_name.data.borrow_mut());
+
+    // the owner's listing price stays with the domain
+    let central_state_signer_seeds: &[&[u8]] = &[&crate::ID.to_bytes(), &[central_state::NONCE]];
+    Cpi::transfer_name_account(
+        accounts.naming_service_program,
+        accounts.central_state,
+        accounts.name,
+        accounts.root_domain,
+        &wrapped_name_key,
+        central_state_signer_seeds,
+        Some(name_record.custom_price),
+    )?;
+    msg!("{}.{} wrapped", params.name, params.root);
+
+    Ok(())
+}
+
+/// Create the token, mint its only unit to the owner and drop the mint authority.
+/// The central state keeps the freeze authority, to freeze the token once the domain is sold.
+fn mint_domain_token(accounts: &Accounts<'_, AccountInfo<'_>>) -> ProgramResult {
+
+    let rent = Rent::get()?;
+    invoke(
+        &system_instruction::create_account(
+            accounts.owner.key,
+            accounts.mint.key,
+            rent.minimum_balance(Mint::LEN),
+            Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        &[accounts.owner.clone(), accounts.mint.clone(), accounts.system_program.clone()],
+    )?;
+
+    invoke(
+        &spl_token::instruction::initialize_mint2(
+            accounts.token_program.key, accounts.mint.key, accounts.owner.key, Some(&central_state::KEY), 0
+        )?,
+        &[accounts.mint.clone(), accounts.token_program.clone()],
+    )?;
+
+    invoke(
+        &create_associated_token_account_idempotent(
+            accounts.owner.key, accounts.owner.key, accounts.mint.key, accounts.token_program.key
+        ),
+        &[
+            accounts.owner.clone(),
+            accounts.owner_token_account.clone(),
+            accounts.mint.clone(),
+            accounts.system_program.clone(),
+            accounts.token_program.clone(),
+            accounts.associated_token_program.clone(),
+        ],
+    )?;
+
+    invoke(
+        &spl_token::instruction::mint_to(
+            accounts.token_program.key, accounts.mint.key, accounts.owner_token_account.key, accounts.owner.key, &[], 1
+        )?,
+        &[
+            accounts.mint.clone(),
+            accounts.owner_token_account.clone(),
+            accounts.owner.clone(),
+            accounts.token_program.clone(),
+        ],
+    )?;
+
+    // supply stays at one for good
+    invoke(
+        &spl_token::instruction::set_authority(
+            accounts.token_program.key, accounts.mint.key, None, AuthorityType::MintTokens, accounts.owner.key, &[]
+        )?,
+        &[accounts.mint.clone(), accounts.owner.clone(), accounts.token_program.clone()],
+    )
+}
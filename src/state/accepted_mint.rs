@@ -0,0 +1,46 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// An SPL token a root domain accepts for bids, set by the admins
+pub struct AcceptedMintRecord {
+    /// false stops new auctions in this token, running ones still settle
+    pub enabled: bool,
+    /// The lowest opening bid (token base units)
+    pub min_price: u64,
+}
+
+impl Sealed for AcceptedMintRecord {}
+
+impl Pack for AcceptedMintRecord {
+    // bool (1) + u64 (8)
+    const LEN: usize = 1 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        AcceptedMintRecord::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize accepted mint record");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+/// Derive PDA for a mint accepted under a root domain
+pub fn get_accepted_mint_key(root_domain: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"accepted_mint", root_domain.as_ref(), mint.as_ref()],
+        &crate::ID,
+    )
+}
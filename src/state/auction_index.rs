@@ -0,0 +1,171 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+
+/// Live auctions a page holds
+pub const AUCTION_PAGE_LEN: usize = 32;
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// A root's auction index, its pages are numbered from 0 up to `pages`
+pub struct AuctionIndexHeader {
+    pub root: Pubkey,
+    /// Pages created so far
+    pub pages: u32,
+    /// Auctions listed across all pages
+    pub live: u32,
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// A live auction as listed in the index
+pub struct AuctionEntry {
+    /// The auction's name state account
+    pub name_state: Pubkey,
+    /// The highest bid, in the auction's currency
+    pub price: u64,
+    /// Unix timestamp the auction can be settled from, seconds
+    pub end_time: i64,
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// The head of an index page, its entries follow it
+pub struct AuctionPageHeader {
+    pub root: Pubkey,
+    pub page: u32,
+    /// Entries held, packed from the front
+    pub len: u16,
+}
+
+impl Sealed for AuctionIndexHeader {}
+
+impl Pack for AuctionIndexHeader {
+    // Pubkey (32) + u32 (4) + u32 (4)
+    const LEN: usize = 32 + 4 + 4;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        AuctionIndexHeader::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize auction index");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl Sealed for AuctionEntry {}
+
+impl Pack for AuctionEntry {
+    // Pubkey (32) + u64 (8) + i64 (8)
+    const LEN: usize = 32 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        AuctionEntry::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize auction entry");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl Sealed for AuctionPageHeader {}
+
+impl Pack for AuctionPageHeader {
+    // Pubkey (32) + u32 (4) + u16 (2)
+    const LEN: usize = 32 + 4 + 2;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        AuctionPageHeader::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize auction page");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl AuctionPageHeader {
+    /// Account size holding `entries` entries
+    pub fn space(entries: usize) -> usize {
+        Self::LEN + entries * AuctionEntry::LEN
+    }
+}
+
+/// An index page read out of its account
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuctionPage {
+    pub header: AuctionPageHeader,
+    pub entries: Vec<AuctionEntry>,
+}
+
+impl AuctionPage {
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let header = AuctionPageHeader::unpack_from_slice(src)?;
+        let len = header.len as usize;
+        if len > AUCTION_PAGE_LEN || src.len() < AuctionPageHeader::space(len) {
+            msg!("auction page shorter than its entries");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let entries = (0..len)
+            .map(|i| {
+                let offset = AuctionPageHeader::space(i);
+                AuctionEntry::unpack_from_slice(&src[offset..offset + AuctionEntry::LEN])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { header, entries })
+    }
+
+    /// Write the page, the slots after the last entry are cleared
+    pub fn pack(&self, dst: &mut [u8]) {
+        let mut header = self.header.clone();
+        header.len = self.entries.len() as u16;
+        header.pack_into_slice(dst);
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let offset = AuctionPageHeader::space(i);
+            entry.pack_into_slice(&mut dst[offset..offset + AuctionEntry::LEN]);
+        }
+        for byte in dst[AuctionPageHeader::space(self.entries.len())..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.entries.len() >= AUCTION_PAGE_LEN
+    }
+
+    pub fn position(&self, name_state: &Pubkey) -> Option<usize> {
+        self.entries.iter().position(|entry| &entry.name_state == name_state)
+    }
+}
+
+/// Derive PDA for a root's auction index
+pub fn get_auction_index_key(root_domain: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"auction_index", root_domain.as_ref()], &crate::ID)
+}
+
+/// Derive PDA for a page of a root's auction index
+pub fn get_auction_page_key(root_domain: &Pubkey, page: u32) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[b"auction_index", root_domain.as_ref(), &page.to_le_bytes()],
+        &crate::ID,
+    )
+}
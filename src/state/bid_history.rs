@@ -0,0 +1,166 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+
+/// Bids a history keeps, older ones are dropped
+pub const MAX_BID_HISTORY: usize = 32;
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// A bid as the history records it
+pub struct BidEntry {
+    pub bidder: Pubkey,
+    /// In the auction's currency
+    pub price: u64,
+    /// Unix timestamp, seconds
+    pub time: i64,
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// The head of an auction's bid history, its entries follow it, oldest first
+pub struct BidHistoryHeader {
+    /// The auction's name state account
+    pub name_state: Pubkey,
+    /// Bids recorded since the auction opened, dropped ones included
+    pub total: u32,
+    /// Entries held, packed from the front
+    pub len: u16,
+}
+
+impl Sealed for BidEntry {}
+
+impl Pack for BidEntry {
+    // Pubkey (32) + u64 (8) + i64 (8)
+    const LEN: usize = 32 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        BidEntry::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize bid entry");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl Sealed for BidHistoryHeader {}
+
+impl Pack for BidHistoryHeader {
+    // Pubkey (32) + u32 (4) + u16 (2)
+    const LEN: usize = 32 + 4 + 2;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        BidHistoryHeader::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize bid history");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl BidHistoryHeader {
+    /// Account size holding `entries` entries
+    pub fn space(entries: usize) -> usize {
+        Self::LEN + entries * BidEntry::LEN
+    }
+}
+
+/// A bid history read out of its account
+#[derive(Clone, Debug, PartialEq)]
+pub struct BidHistory {
+    pub header: BidHistoryHeader,
+    pub entries: Vec<BidEntry>,
+}
+
+impl BidHistory {
+    pub fn new(name_state: Pubkey) -> Self {
+        Self {
+            header: BidHistoryHeader { name_state, total: 0, len: 0 },
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let header = BidHistoryHeader::unpack_from_slice(src)?;
+        let len = header.len as usize;
+        if len > MAX_BID_HISTORY || src.len() < BidHistoryHeader::space(len) {
+            msg!("bid history shorter than its entries");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let entries = (0..len)
+            .map(|i| {
+                let offset = BidHistoryHeader::space(i);
+                BidEntry::unpack_from_slice(&src[offset..offset + BidEntry::LEN])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { header, entries })
+    }
+
+    /// Write the history, the slots after the last entry are cleared
+    pub fn pack(&self, dst: &mut [u8]) {
+        let mut header = self.header.clone();
+        header.len = self.entries.len() as u16;
+        header.pack_into_slice(dst);
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let offset = BidHistoryHeader::space(i);
+            entry.pack_into_slice(&mut dst[offset..offset + BidEntry::LEN]);
+        }
+        for byte in dst[BidHistoryHeader::space(self.entries.len())..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    /// Record a bid, dropping the oldest once the history is full
+    pub fn push(&mut self, entry: BidEntry) -> Result<(), ProgramError> {
+        if self.entries.len() >= MAX_BID_HISTORY {
+            self.entries.remove(0);
+        }
+        self.entries.push(entry);
+        self.header.total = self.header.total.checked_add(1).ok_or(ProgramError::InvalidArgument)?;
+        Ok(())
+    }
+}
+
+/// Derive PDA for an auction's bid history
+pub fn get_bid_history_key(name_state: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"bid_history", name_state.as_ref()], &crate::ID)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn full_history_keeps_the_latest_bids() {
+        let mut history = BidHistory::new(Pubkey::new_unique());
+        let bidder = Pubkey::new_unique();
+        for i in 0..MAX_BID_HISTORY as u64 + 3 {
+            history.push(BidEntry { bidder, price: 10 + i, time: i as i64 }).unwrap();
+        }
+        assert_eq!(history.entries.len(), MAX_BID_HISTORY);
+        assert_eq!(history.header.total, MAX_BID_HISTORY as u32 + 3);
+        assert_eq!(history.entries.first().unwrap().price, 13);
+
+        let mut data = vec![0xff; BidHistoryHeader::space(MAX_BID_HISTORY)];
+        history.pack(&mut data);
+        let read = BidHistory::unpack(&data).unwrap();
+        assert_eq!(read.entries, history.entries);
+        assert_eq!(read.header.len as usize, MAX_BID_HISTORY);
+    }
+}
@@ -0,0 +1,138 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+
+use crate::{constants::{ADMIN_ANDY, ADMIN_FANMOCHENG}, utils::DEFAULT_REFERRER_MATURITY};
+
+/// Most ancestor levels the referral share table can pay
+pub const MAX_REFERRAL_DEPTH: usize = 8;
+/// The split used while no table is configured: 52%, 26%, 13%
+pub const DEFAULT_REFERRAL_SHARES: [u64; 3] = [520_000_000, 260_000_000, 130_000_000];
+
+/// Most performance tiers a referrer can climb
+pub const MAX_TIERS: usize = 4;
+
+/// Most wallets admin withdrawals are split between
+pub const MAX_TREASURY_RECIPIENTS: usize = 8;
+/// Treasury shares are in basis points and add up to this
+pub const TREASURY_BPS: u16 = 10_000;
+/// The split used while no table is configured: half to each admin
+pub const DEFAULT_TREASURY: [TreasuryShare; 2] = [
+    TreasuryShare { recipient: ADMIN_ANDY, bps: 5_000 },
+    TreasuryShare { recipient: ADMIN_FANMOCHENG, bps: 5_000 },
+];
+
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// A wallet's cut of every admin withdrawal
+pub struct TreasuryShare {
+    pub recipient: Pubkey,
+    /// Basis points of the withdrawal (10,000 = 100%)
+    pub bps: u16,
+}
+
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// A rank a referrer reaches through performance
+pub struct Tier {
+    /// Performance needed to reach the tier (lamports)
+    pub threshold: u64,
+    /// Extra share of each payment credited on top of the level's share (1e9 = 100%)
+    pub rate: u64,
+}
+
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// Protocol parameters the admins can tune without a redeploy
+pub struct ConfigRecord {
+    /// Fee charged for every year a registration is renewed (lamports)
+    pub renew_fee_per_year: u64,
+    /// How long after expiry the owner can still renew before the name lapses (seconds)
+    pub grace_period: i64,
+    /// Share of the auction price credited to whoever settles it (1e9 = 100%)
+    pub settle_tip_rate: u64,
+    /// How many ancestor levels share a payment, 0 for the default table
+    pub referral_depth: u8,
+    /// Share of each ancestor level, nearest first (1e9 = 100%) -- what is left stays with the vault
+    pub referral_shares: [u64; MAX_REFERRAL_DEPTH],
+    /// How long a usr waits between changes of referrer, counted from sign-up for the first one (seconds)
+    pub referrer_change_cooldown: i64,
+    /// How old a record must be before others can sign up under it, 0 for the default (seconds)
+    pub referrer_maturity: i64,
+    /// How many tiers are in use, 0 for none
+    pub tier_count: u8,
+    /// Tiers from the lowest up, thresholds strictly increasing
+    pub tiers: [Tier; MAX_TIERS],
+    /// How many treasury recipients are in use, 0 for the default split
+    pub treasury_count: u8,
+    /// Recipients of admin withdrawals, shares adding up to 10,000 basis points
+    pub treasury: [TreasuryShare; MAX_TREASURY_RECIPIENTS],
+}
+
+impl Sealed for ConfigRecord {}
+
+impl ConfigRecord {
+    /// The config, or the defaults while the admins haven't written one
+    pub fn load(account: &AccountInfo) -> Result<Self, ProgramError> {
+        if account.data_is_empty() {
+            return Ok(Self::default());
+        }
+        Self::unpack_from_slice(&account.data.borrow())
+    }
+
+    pub fn referral_shares(&self) -> &[u64] {
+        if self.referral_depth == 0 {
+            return &DEFAULT_REFERRAL_SHARES;
+        }
+        let depth = (self.referral_depth as usize).min(MAX_REFERRAL_DEPTH);
+        &self.referral_shares[..depth]
+    }
+
+    pub fn tiers(&self) -> &[Tier] {
+        let count = (self.tier_count as usize).min(MAX_TIERS);
+        &self.tiers[..count]
+    }
+
+    pub fn treasury(&self) -> &[TreasuryShare] {
+        if self.treasury_count == 0 {
+            return &DEFAULT_TREASURY;
+        }
+        let count = (self.treasury_count as usize).min(MAX_TREASURY_RECIPIENTS);
+        &self.treasury[..count]
+    }
+
+    pub fn referrer_maturity(&self) -> i64 {
+        if self.referrer_maturity == 0 {
+            return DEFAULT_REFERRER_MATURITY;
+        }
+        self.referrer_maturity
+    }
+}
+
+impl Pack for ConfigRecord {
+    // u64 (8) + i64 (8) + u64 (8) + u8 (1) + MAX_REFERRAL_DEPTH * u64 (8) + i64 (8) + i64 (8)
+    // + u8 (1) + MAX_TIERS * (u64 (8) + u64 (8)) + u8 (1) + MAX_TREASURY_RECIPIENTS * (Pubkey (32) + u16 (2))
+    const LEN: usize = 8 + 8 + 8 + 1 + MAX_REFERRAL_DEPTH * 8 + 8 + 8 + 1 + MAX_TIERS * 16
+        + 1 + MAX_TREASURY_RECIPIENTS * 34;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    // fields added after the config was written read as zero until the admins set them
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut buf = [0u8; Self::LEN];
+        let len = src.len().min(Self::LEN);
+        buf[..len].copy_from_slice(&src[..len]);
+
+        let mut p: &[u8] = &buf;
+        ConfigRecord::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize ConfigRecord");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
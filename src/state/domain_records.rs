@@ -0,0 +1,237 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use web3_domain_name_service::state::NameRecordHeader;
+
+/// Records a domain can hold
+pub const MAX_DOMAIN_RECORDS: usize = 16;
+/// Longest URL, CID or text value (bytes)
+pub const MAX_RECORD_VALUE_LEN: usize = 256;
+/// Longest text record key (bytes)
+pub const MAX_TEXT_KEY_LEN: usize = 32;
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// A typed piece of data attached to a domain
+pub enum DomainRecord {
+    /// A Solana address
+    Sol(Pubkey),
+    /// An Ethereum address, 20 raw bytes
+    Eth([u8; 20]),
+    /// An http(s) URL
+    Url(String),
+    /// An IPFS content id, v0 (`Qm...`) or v1 base32 (`b...`)
+    IpfsCid(String),
+    /// A free-form key and value, e.g. `email` or `twitter`
+    Text { key: String, value: String },
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// Where a record sits: one of each address kind, one text record per key
+pub enum RecordSlot {
+    Sol,
+    Eth,
+    Url,
+    IpfsCid,
+    Text(String),
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// One change `UpdateRecords` makes
+pub enum RecordUpdate {
+    /// Write the record, replacing the one in its slot
+    Set(DomainRecord),
+    /// Drop the record in the slot
+    Clear(RecordSlot),
+}
+
+impl DomainRecord {
+    pub fn slot(&self) -> RecordSlot {
+        match self {
+            DomainRecord::Sol(_) => RecordSlot::Sol,
+            DomainRecord::Eth(_) => RecordSlot::Eth,
+            DomainRecord::Url(_) => RecordSlot::Url,
+            DomainRecord::IpfsCid(_) => RecordSlot::IpfsCid,
+            DomainRecord::Text { key, .. } => RecordSlot::Text(key.clone()),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ProgramError> {
+        let valid = match self {
+            DomainRecord::Sol(_) | DomainRecord::Eth(_) => true,
+            DomainRecord::Url(url) => {
+                url.len() <= MAX_RECORD_VALUE_LEN
+                    && (url.starts_with("https://") || url.starts_with("http://"))
+            }
+            DomainRecord::IpfsCid(cid) => is_ipfs_cid(cid),
+            DomainRecord::Text { key, value } => {
+                !key.is_empty() && key.len() <= MAX_TEXT_KEY_LEN && value.len() <= MAX_RECORD_VALUE_LEN
+            }
+        };
+        if !valid {
+            msg!("invalid record: {:?}", self);
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    /// The value as a client shows it: base58 for Solana, 0x hex for Ethereum
+    pub fn display_value(&self) -> String {
+        match self {
+            DomainRecord::Sol(address) => address.to_string(),
+            DomainRecord::Eth(address) => {
+                let hex: String = address.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("0x{}", hex)
+            }
+            DomainRecord::Url(value) | DomainRecord::IpfsCid(value) => value.clone(),
+            DomainRecord::Text { value, .. } => value.clone(),
+        }
+    }
+}
+
+// CIDv0 is 46 base58 characters, CIDv1 in base32 is lower case and starts with `b`
+fn is_ipfs_cid(cid: &str) -> bool {
+    let base58 = |c: char| c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l');
+    let base32 = |c: char| c.is_ascii_lowercase() || ('2'..='7').contains(&c);
+
+    if cid.starts_with("Qm") {
+        cid.len() == 46 && cid.chars().all(base58)
+    } else {
+        cid.starts_with('b') && cid.len() > 8 && cid.len() <= MAX_RECORD_VALUE_LEN && cid.chars().all(base32)
+    }
+}
+
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// The records of one domain
+pub struct DomainRecordsAccount {
+    /// The domain name account
+    pub domain: Pubkey,
+    /// The owner who wrote the records -- they stop resolving once the domain changes hands
+    pub owner: Pubkey,
+    pub records: Vec<DomainRecord>,
+}
+
+impl DomainRecordsAccount {
+    /// Account size the records need
+    pub fn space(&self) -> usize {
+        self.try_to_vec().map(|data| data.len()).unwrap_or(0)
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        DomainRecordsAccount::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize domain records");
+            ProgramError::InvalidAccountData
+        })
+    }
+
+    /// Write the records, clearing what a longer set left behind
+    pub fn pack(&self, dst: &mut [u8]) {
+        let data = self.try_to_vec().unwrap();
+        dst[..data.len()].copy_from_slice(&data);
+        for byte in dst[data.len()..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    /// Set a record, replacing the one in its slot
+    pub fn set(&mut self, record: DomainRecord) -> Result<(), ProgramError> {
+        record.validate()?;
+        let slot = record.slot();
+        match self.records.iter().position(|held| held.slot() == slot) {
+            Some(i) => self.records[i] = record,
+            None if self.records.len() >= MAX_DOMAIN_RECORDS => {
+                msg!("a domain holds {} records at most", MAX_DOMAIN_RECORDS);
+                return Err(ProgramError::InvalidArgument);
+            }
+            None => self.records.push(record),
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self, slot: &RecordSlot) {
+        self.records.retain(|held| &held.slot() != slot);
+    }
+
+    pub fn apply(&mut self, update: RecordUpdate) -> Result<(), ProgramError> {
+        match update {
+            RecordUpdate::Set(record) => self.set(record),
+            RecordUpdate::Clear(slot) => {
+                self.clear(&slot);
+                Ok(())
+            }
+        }
+    }
+
+    pub fn sol(&self) -> Option<Pubkey> {
+        self.records.iter().find_map(|record| match record {
+            DomainRecord::Sol(address) => Some(*address),
+            _ => None,
+        })
+    }
+
+    pub fn text(&self, key: &str) -> Option<&str> {
+        self.records.iter().find_map(|record| match record {
+            DomainRecord::Text { key: k, value } if k == key => Some(value.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// A domain's records, given its records account and its name account's key and data --
+/// empty when they were written by someone who no longer owns the domain
+pub fn resolve_domain_records(
+    records_account: &[u8],
+    name_account_key: &Pubkey,
+    name_account: &[u8],
+) -> Result<Vec<DomainRecord>, ProgramError> {
+    let records = DomainRecordsAccount::unpack(records_account)?;
+    if &records.domain != name_account_key {
+        msg!("records are for {}, not {}", records.domain, name_account_key);
+        return Err(ProgramError::InvalidArgument);
+    }
+    let name_record = NameRecordHeader::unpack_from_slice(name_account)?;
+    if name_record.owner != records.owner {
+        return Ok(Vec::new());
+    }
+    Ok(records.records)
+}
+
+/// Derive PDA for a domain's records
+pub fn get_domain_records_key(name_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"domain_records", name_account.as_ref()], &crate::ID)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn records_replace_their_slot_and_validate() {
+        let mut account = DomainRecordsAccount::default();
+        account.set(DomainRecord::Sol(Pubkey::new_unique())).unwrap();
+        let sol = Pubkey::new_unique();
+        account.set(DomainRecord::Sol(sol)).unwrap();
+        account.set(DomainRecord::Text { key: "email".into(), value: "a@b.c".into() }).unwrap();
+        account.set(DomainRecord::Text { key: "twitter".into(), value: "@a".into() }).unwrap();
+        account.set(DomainRecord::IpfsCid("QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG".into())).unwrap();
+
+        assert_eq!(account.records.len(), 4);
+        assert_eq!(account.sol(), Some(sol));
+        assert_eq!(account.text("email"), Some("a@b.c"));
+
+        assert!(account.set(DomainRecord::Url("ftp://x".into())).is_err());
+        assert!(account.set(DomainRecord::IpfsCid("Qm123".into())).is_err());
+
+        account.apply(RecordUpdate::Clear(RecordSlot::Text("email".into()))).unwrap();
+        assert_eq!(account.text("email"), None);
+        assert_eq!(DomainRecord::Eth([0xab; 20]).display_value(), format!("0x{}", "ab".repeat(20)));
+
+        let mut data = vec![0u8; account.space() + 10];
+        account.pack(&mut data);
+        assert_eq!(DomainRecordsAccount::unpack(&data).unwrap(), account);
+    }
+}
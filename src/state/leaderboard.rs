@@ -0,0 +1,197 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+use web3_utils::borsh_size::BorshSize;
+
+/// Most entries a leaderboard can be sized to
+pub const MAX_LEADERBOARD_LEN: u16 = 100;
+
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// What a leaderboard ranks
+pub enum LeaderboardKind {
+    /// Domain name accounts by their latest lamport settlement price
+    Domains,
+    /// Referrer wallets by performance
+    Referrers,
+}
+
+impl BorshSize for LeaderboardKind {
+    fn borsh_len(&self) -> usize {
+        1
+    }
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// One ranked domain or referrer
+pub struct LeaderboardEntry {
+    /// The domain name account, or the referrer's wallet
+    pub key: Pubkey,
+    /// Settlement price or performance (lamports)
+    pub value: u64,
+    /// Unix timestamp the value was reached, seconds
+    pub time: i64,
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// The head of a leaderboard, its entries follow highest first
+pub struct LeaderboardHeader {
+    pub kind: LeaderboardKind,
+    /// Entries the account has room for
+    pub capacity: u16,
+    /// Entries held
+    pub len: u16,
+}
+
+impl Sealed for LeaderboardEntry {}
+
+impl Pack for LeaderboardEntry {
+    // Pubkey (32) + u64 (8) + i64 (8)
+    const LEN: usize = 32 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        LeaderboardEntry::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize leaderboard entry");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl Sealed for LeaderboardHeader {}
+
+impl Pack for LeaderboardHeader {
+    // enum (1) + u16 (2) + u16 (2)
+    const LEN: usize = 1 + 2 + 2;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        LeaderboardHeader::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize leaderboard header");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl LeaderboardHeader {
+    /// Account size holding `capacity` entries
+    pub fn space(capacity: u16) -> usize {
+        Self::LEN + capacity as usize * LeaderboardEntry::LEN
+    }
+}
+
+/// A leaderboard read out of its account
+#[derive(Clone, Debug, PartialEq)]
+pub struct Leaderboard {
+    pub header: LeaderboardHeader,
+    /// Highest value first, one entry per key
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+impl Leaderboard {
+    pub fn new(kind: LeaderboardKind, capacity: u16) -> Self {
+        Self {
+            header: LeaderboardHeader { kind, capacity, len: 0 },
+            entries: Vec::with_capacity(capacity as usize),
+        }
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let header = LeaderboardHeader::unpack_from_slice(src)?;
+        if header.len > header.capacity || src.len() < LeaderboardHeader::space(header.capacity) {
+            msg!("leaderboard shorter than its entries");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let entries = (0..header.len as usize)
+            .map(|i| {
+                let offset = LeaderboardHeader::LEN + i * LeaderboardEntry::LEN;
+                LeaderboardEntry::unpack_from_slice(&src[offset..offset + LeaderboardEntry::LEN])
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { header, entries })
+    }
+
+    /// Write the leaderboard, the slots after the last entry are cleared
+    pub fn pack(&self, dst: &mut [u8]) {
+        self.header.pack_into_slice(dst);
+        for (i, entry) in self.entries.iter().enumerate() {
+            let offset = LeaderboardHeader::LEN + i * LeaderboardEntry::LEN;
+            entry.pack_into_slice(&mut dst[offset..offset + LeaderboardEntry::LEN]);
+        }
+        let end = LeaderboardHeader::space(self.header.len);
+        for byte in dst[end..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    /// Rank a key at its new value, replacing the entry it already had.
+    /// Equal values keep the earlier entry ahead.
+    pub fn submit(&mut self, entry: LeaderboardEntry) {
+        self.entries.retain(|held| held.key != entry.key);
+
+        let rank = self.entries.partition_point(|held| held.value >= entry.value);
+        if rank < self.header.capacity as usize {
+            self.entries.insert(rank, entry);
+        }
+        self.resize(self.header.capacity);
+    }
+
+    /// Change the capacity, dropping the lowest entries that no longer fit
+    pub fn resize(&mut self, capacity: u16) {
+        self.entries.truncate(capacity as usize);
+        self.header.capacity = capacity;
+        self.header.len = self.entries.len() as u16;
+    }
+}
+
+/// Derive PDA for a leaderboard
+pub fn get_leaderboard_key(kind: LeaderboardKind) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"leaderboard", &[kind as u8]], &crate::ID)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn leaderboard_stays_sorted_and_keeps_one_entry_per_key() {
+        let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let entry = |i: usize, value| LeaderboardEntry { key: keys[i], value, time: 0 };
+
+        let mut board = Leaderboard::new(LeaderboardKind::Domains, 3);
+        board.submit(entry(0, 10));
+        board.submit(entry(1, 30));
+        board.submit(entry(2, 20));
+        // too low for a full board
+        board.submit(entry(3, 5));
+        // a resale moves the domain instead of listing it twice
+        board.submit(entry(0, 40));
+
+        let ranked: Vec<_> = board.entries.iter().map(|e| (e.key, e.value)).collect();
+        assert_eq!(ranked, vec![(keys[0], 40), (keys[1], 30), (keys[2], 20)]);
+
+        let mut data = vec![0u8; LeaderboardHeader::space(3)];
+        board.pack(&mut data);
+        assert_eq!(Leaderboard::unpack(&data).unwrap(), board);
+
+        board.resize(2);
+        assert_eq!(board.header.len, 2);
+        assert_eq!(board.entries[1].key, keys[1]);
+    }
+}
@@ -0,0 +1,165 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+
+/// Entries a ledger keeps, the oldest is overwritten after that
+pub const MAX_LEDGER_ENTRIES: usize = 64;
+
+#[derive(Clone, Copy, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// What moved a referrer record's profit
+pub enum LedgerKind {
+    /// A level's share of a payment made down the referral chain
+    ReferralShare,
+    /// A seller's share of a resale
+    SaleProceeds,
+    /// The tip for settling an auction
+    SettleTip,
+    /// A fee for a subdomain created under an owned domain
+    SubdomainFee,
+    /// Profit taken out, the withdrawal fee included
+    Withdrawal,
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// One change to a referrer record's profit
+pub struct LedgerEntry {
+    pub kind: LedgerKind,
+    /// The domain name account the profit came from -- default for withdrawals
+    pub domain: Pubkey,
+    /// The token the amount is in -- default means lamports
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// Unix timestamp, seconds
+    pub time: i64,
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// The head of a ledger, the entries follow it
+pub struct LedgerHeader {
+    /// The referrer record this ledger belongs to
+    pub record: Pubkey,
+    /// Entries ever appended -- the next one goes to slot `count % MAX_LEDGER_ENTRIES`
+    pub count: u64,
+}
+
+impl Sealed for LedgerEntry {}
+
+impl Pack for LedgerEntry {
+    // enum (1) + Pubkey (32) + Pubkey (32) + u64 (8) + i64 (8)
+    const LEN: usize = 1 + 32 + 32 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        LedgerEntry::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize ledger entry");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl Sealed for LedgerHeader {}
+
+impl Pack for LedgerHeader {
+    // Pubkey (32) + u64 (8)
+    const LEN: usize = 32 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        LedgerHeader::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize ledger header");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl LedgerHeader {
+    /// Entries currently held
+    pub fn len(&self) -> usize {
+        (self.count as usize).min(MAX_LEDGER_ENTRIES)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// The slot the next entry is written to
+    pub fn next_slot(&self) -> usize {
+        (self.count % MAX_LEDGER_ENTRIES as u64) as usize
+    }
+
+    /// Account size holding `entries` entries
+    pub fn space(entries: usize) -> usize {
+        Self::LEN + entries * LedgerEntry::LEN
+    }
+}
+
+/// Read a ledger account's entries, oldest first
+pub fn unpack_ledger(src: &[u8]) -> Result<(LedgerHeader, Vec<LedgerEntry>), ProgramError> {
+    let header = LedgerHeader::unpack_from_slice(src)?;
+    let len = header.len();
+    if src.len() < LedgerHeader::space(len) {
+        msg!("ledger shorter than its entries");
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    // once full, the slot about to be overwritten holds the oldest entry
+    let first = if len < MAX_LEDGER_ENTRIES { 0 } else { header.next_slot() };
+    let entries = (0..len)
+        .map(|i| {
+            let offset = LedgerHeader::space((first + i) % MAX_LEDGER_ENTRIES);
+            LedgerEntry::unpack_from_slice(&src[offset..offset + LedgerEntry::LEN])
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok((header, entries))
+}
+
+/// Derive PDA for a referrer record's ledger
+pub fn get_ledger_key(referrer_record: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"ledger", referrer_record.as_ref()], &crate::ID)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ledger_reads_oldest_first_after_wrapping() {
+        let entry = |amount| LedgerEntry {
+            kind: LedgerKind::ReferralShare,
+            domain: Pubkey::default(),
+            mint: Pubkey::default(),
+            amount,
+            time: 0,
+        };
+
+        let mut data = vec![0u8; LedgerHeader::space(MAX_LEDGER_ENTRIES)];
+        let mut header = LedgerHeader { record: Pubkey::new_unique(), count: 0 };
+        for amount in 0..MAX_LEDGER_ENTRIES as u64 + 3 {
+            let offset = LedgerHeader::space(header.next_slot());
+            entry(amount).pack_into_slice(&mut data[offset..offset + LedgerEntry::LEN]);
+            header.count += 1;
+        }
+        header.pack_into_slice(&mut data);
+
+        let (_, entries) = unpack_ledger(&data).unwrap();
+        assert_eq!(entries.len(), MAX_LEDGER_ENTRIES);
+        assert_eq!(entries[0].amount, 3);
+        assert_eq!(entries[MAX_LEDGER_ENTRIES - 1].amount, MAX_LEDGER_ENTRIES as u64 + 2);
+    }
+}
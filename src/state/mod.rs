@@ -4,10 +4,25 @@ pub mod name_state;
 pub mod referrer_record;
 pub mod vault;
 pub mod reverse_lookup;
+pub mod config;
+pub mod registration;
+pub mod subdomain;
+pub mod accepted_mint;
+pub mod ledger;
+pub mod treasury;
+pub mod leaderboard;
+pub mod stats;
+pub mod auction_index;
+pub mod portfolio;
+pub mod primary_name;
+pub mod domain_records;
+pub mod wrapped;
+pub mod bid_history;
 
 pub use root_state::*;
 pub use name_state::*;
 pub use referrer_record::*;
+pub use registration::*;
 
 use solana_program::{
     account_info::AccountInfo,
@@ -8,9 +8,21 @@ use solana_program::{
 };
 use web3_domain_name_service::utils::get_seeds_and_key;
 
-use crate::{central_state, utils::get_hashed_name};
+use crate::{central_state, utils::{TIME_LIMIT, get_hashed_name}};
 
 
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// What the auction sells
+pub enum AuctionKind {
+    /// Opened before the kind was recorded
+    #[default]
+    Unknown,
+    /// A name nobody holds: a new one, or a lapsed one taken back by the registrar
+    Registration,
+    /// A held name, bought at its owner's custom price
+    Resale,
+}
+
 #[derive(Clone,Debug, BorshSerialize, BorshDeserialize, PartialEq)]
 pub struct NameStateRecordHeader {
     /// The public key of the highest bidder
@@ -23,13 +35,28 @@ pub struct NameStateRecordHeader {
     pub root: [u8; 16],
     /// Subdomain name
     pub name: [u8; 32],
+    /// The SPL token the auction is paid in -- default means lamports
+    pub mint: Pubkey,
+    /// Who opened the auction and paid this account's rent -- default on older states,
+    /// whose rent was taken out of the price
+    pub opener: Pubkey,
+    /// When the auction was opened -- 0 on states opened before it was recorded
+    pub start_time: i64,
+    /// Bids placed, the opening one included -- older states count from their first bid after the upgrade
+    pub bid_count: u32,
+    /// When the auction can be settled, unless someone bids again
+    pub end_time: i64,
+    pub kind: AuctionKind,
+    /// The index page listing the auction -- none on states opened before it was recorded
+    pub auction_page: Option<u32>,
 }
 
 impl Sealed for NameStateRecordHeader {}
 
 impl NameStateRecordHeader {
     pub fn new(
-        highest_bidder: &Pubkey, update_time: i64, highest_price: u64, root: &str, name: &str
+        highest_bidder: &Pubkey, update_time: i64, highest_price: u64, root: &str, name: &str, mint: &Pubkey,
+        kind: AuctionKind,
     ) -> Self {
         let mut root_buf = [0u8; 16];
         let root_bytes = root.as_bytes();
@@ -47,12 +74,64 @@ impl NameStateRecordHeader {
             highest_price: highest_price, 
             root: root_buf,
             name: name_buf,
+            mint: *mint,
+            // the opener places the opening bid
+            opener: *highest_bidder,
+            start_time: update_time,
+            bid_count: 1,
+            end_time: update_time.saturating_add(TIME_LIMIT),
+            kind,
+            auction_page: None,
         }
     }
+
+    /// Take a higher bid, the auction runs for another `TIME_LIMIT` from now
+    pub fn place_bid(&mut self, bidder: &Pubkey, price: u64, now: i64) -> Result<(), ProgramError> {
+        self.highest_bidder = *bidder;
+        self.highest_price = price;
+        self.update_time = now;
+        self.bid_count = self.bid_count.checked_add(1).ok_or(ProgramError::InvalidArgument)?;
+        self.end_time = now.checked_add(TIME_LIMIT).ok_or(ProgramError::InvalidArgument)?;
+        Ok(())
+    }
+
+    pub fn is_token_auction(&self) -> bool {
+        self.mint != Pubkey::default()
+    }
+
+    pub fn is_resale(&self) -> bool {
+        self.kind == AuctionKind::Resale
+    }
+
+    /// The domain, without the root
+    pub fn name(&self) -> String {
+        fixed_str(&self.name)
+    }
+
+    pub fn root(&self) -> String {
+        fixed_str(&self.root)
+    }
+}
+
+/// A zero-padded name field as a string
+fn fixed_str(buf: &[u8]) -> String {
+    let len = buf.iter().position(|byte| *byte == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+impl NameStateRecordHeader {
+    /// Auctions opened before token payments have no mint
+    pub const LEGACY_LEN: usize = 96;
+    /// Auctions opened before the start time, bid count, end time and kind were recorded,
+    /// a bid grows them to the current layout
+    pub const OPENER_LEN: usize = 160;
+    /// Auctions opened before their index page was recorded
+    pub const UNPAGED_LEN: usize = Self::OPENER_LEN + 8 + 4 + 8 + 1;
 }
 
 impl Pack for  NameStateRecordHeader {
-    const LEN: usize = 96;
+    /// UNPAGED_LEN + 5 (auction_page, an option tag and a u32)
+    const LEN: usize = Self::UNPAGED_LEN + 5;
 
     fn pack_into_slice(&self, dst: &mut [u8]) {
         let mut slice = dst;
@@ -60,11 +139,27 @@ impl Pack for  NameStateRecordHeader {
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let mut p = src;
-        NameStateRecordHeader::deserialize(&mut p).map_err(|_| {
+        if src.len() < Self::LEGACY_LEN {
+            msg!("Invalid data length for name state record");
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        // a legacy state reads as a lamport auction
+        let mut buf = [0u8; Self::LEN];
+        let len = src.len().min(Self::LEN);
+        buf[..len].copy_from_slice(&src[..len]);
+
+        let mut p: &[u8] = &buf;
+        let mut state = NameStateRecordHeader::deserialize(&mut p).map_err(|_| {
             msg!("Failed to deserialize name state record");
             ProgramError::InvalidAccountData
-        })
+        })?;
+
+        // an older state ends a `TIME_LIMIT` after its last bid
+        if src.len() < Self::UNPAGED_LEN {
+            state.end_time = state.update_time.saturating_add(TIME_LIMIT);
+        }
+        Ok(state)
     }
 }
 
@@ -78,4 +173,37 @@ pub fn get_name_state_key(
         Some(&central_state::KEY), 
         Some(root_domain_key)
     )
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn older_states_read_with_their_end_time() {
+        let opener = Pubkey::new_unique();
+        let mut state = NameStateRecordHeader::new(
+            &opener, 1_700_000_000, 10_000_000, "web3", "alice", &Pubkey::default(), AuctionKind::Resale,
+        );
+        state.auction_page = Some(3);
+        let mut data = [0u8; NameStateRecordHeader::LEN];
+        state.pack_into_slice(&mut data);
+        assert_eq!(NameStateRecordHeader::unpack_from_slice(&data).unwrap(), state);
+
+        let unpaged = NameStateRecordHeader::unpack_from_slice(&data[..NameStateRecordHeader::UNPAGED_LEN]).unwrap();
+        assert_eq!((unpaged.auction_page, unpaged.end_time), (None, state.end_time));
+        assert_eq!((state.name(), state.root()), ("alice".to_string(), "web3".to_string()));
+
+        let bidder = Pubkey::new_unique();
+        state.place_bid(&bidder, 20_000_000, 1_700_000_100).unwrap();
+        assert_eq!((state.bid_count, state.end_time, state.start_time), (2, 1_700_000_100 + TIME_LIMIT, 1_700_000_000));
+
+        state.pack_into_slice(&mut data);
+        let older = NameStateRecordHeader::unpack_from_slice(&data[..NameStateRecordHeader::OPENER_LEN]).unwrap();
+        assert_eq!((older.highest_bidder, older.opener), (bidder, opener));
+        assert_eq!((older.start_time, older.bid_count, older.kind), (0, 0, AuctionKind::Unknown));
+        assert_eq!(older.end_time, 1_700_000_100 + TIME_LIMIT);
+
+        let legacy = NameStateRecordHeader::unpack_from_slice(&data[..NameStateRecordHeader::LEGACY_LEN]).unwrap();
+        assert_eq!((legacy.opener, legacy.end_time), (Pubkey::default(), 1_700_000_100 + TIME_LIMIT));
+    }
+}
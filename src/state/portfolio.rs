@@ -0,0 +1,107 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Domains a portfolio lists, wins past that aren't listed
+pub const MAX_PORTFOLIO_DOMAINS: usize = 128;
+/// Open bids a portfolio lists
+pub const MAX_PORTFOLIO_BIDS: usize = 64;
+
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// A wallet's domains won through the registrar and the auctions it leads
+pub struct PortfolioRecord {
+    pub owner: Pubkey,
+    /// Domain name accounts, oldest first
+    pub domains: Vec<Pubkey>,
+    /// Name state accounts of the auctions the wallet is the highest bidder in
+    pub bids: Vec<Pubkey>,
+}
+
+impl PortfolioRecord {
+    pub fn new(owner: Pubkey) -> Self {
+        Self { owner, ..Self::default() }
+    }
+
+    /// Account size the record needs -- the account only grows, the rest reads as padding
+    pub fn space(&self) -> usize {
+        32 + 4 + self.domains.len() * 32 + 4 + self.bids.len() * 32
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        PortfolioRecord::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize portfolio");
+            ProgramError::InvalidAccountData
+        })
+    }
+
+    /// Write the record, clearing what an earlier, longer one left behind
+    pub fn pack(&self, dst: &mut [u8]) {
+        let data = self.try_to_vec().unwrap();
+        dst[..data.len()].copy_from_slice(&data);
+        for byte in dst[data.len()..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    pub fn add_domain(&mut self, domain: Pubkey) {
+        add_capped(&mut self.domains, domain, MAX_PORTFOLIO_DOMAINS, "domains");
+    }
+
+    pub fn remove_domain(&mut self, domain: &Pubkey) {
+        self.domains.retain(|held| held != domain);
+    }
+
+    pub fn add_bid(&mut self, name_state: Pubkey) {
+        add_capped(&mut self.bids, name_state, MAX_PORTFOLIO_BIDS, "bids");
+    }
+
+    pub fn remove_bid(&mut self, name_state: &Pubkey) {
+        self.bids.retain(|held| held != name_state);
+    }
+}
+
+// a full list stops listing, the sale or bid itself goes through
+fn add_capped(list: &mut Vec<Pubkey>, key: Pubkey, cap: usize, what: &str) {
+    if list.contains(&key) {
+        return;
+    }
+    if list.len() >= cap {
+        msg!("portfolio lists {} {} at most, {} not listed", cap, what, key);
+        return;
+    }
+    list.push(key);
+}
+
+/// Derive PDA for a wallet's portfolio
+pub fn get_portfolio_key(owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"portfolio", owner.as_ref()], &crate::ID)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn portfolio_moves_a_won_bid_to_domains() {
+        let (name_state, domain) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut portfolio = PortfolioRecord::new(Pubkey::new_unique());
+        portfolio.add_bid(name_state);
+        portfolio.add_bid(name_state);
+        assert_eq!(portfolio.bids.len(), 1);
+
+        let mut data = vec![0u8; portfolio.space()];
+        portfolio.pack(&mut data);
+
+        portfolio.remove_bid(&name_state);
+        portfolio.add_domain(domain);
+        // same size, the bid's slot now holds the domain
+        portfolio.pack(&mut data);
+        let read = PortfolioRecord::unpack(&data).unwrap();
+        assert_eq!(read, portfolio);
+        assert_eq!((read.domains, read.bids), (vec![domain], vec![]));
+    }
+}
@@ -0,0 +1,120 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Pack,
+    pubkey::Pubkey,
+};
+use web3_domain_name_service::state::NameRecordHeader;
+
+#[derive(Clone, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// The domain a wallet presents as its identity
+pub struct PrimaryNameRecord {
+    pub wallet: Pubkey,
+    /// The domain name account -- default once the domain left the wallet
+    pub name_account: Pubkey,
+    /// The domain, without the root
+    pub name: String,
+    pub root: String,
+}
+
+impl PrimaryNameRecord {
+    /// Account size the record needs
+    pub fn space(&self) -> usize {
+        32 + 32 + 4 + self.name.len() + 4 + self.root.len()
+    }
+
+    pub fn unpack(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        PrimaryNameRecord::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize primary name");
+            ProgramError::InvalidAccountData
+        })
+    }
+
+    /// Write the record, clearing what a longer one left behind
+    pub fn pack(&self, dst: &mut [u8]) {
+        let data = self.try_to_vec().unwrap();
+        dst[..data.len()].copy_from_slice(&data);
+        for byte in dst[data.len()..].iter_mut() {
+            *byte = 0;
+        }
+    }
+
+    pub fn is_set(&self) -> bool {
+        self.name_account != Pubkey::default()
+    }
+
+    /// Forget the domain, the wallet has to pick a primary name again
+    pub fn invalidate(&mut self) {
+        *self = Self { wallet: self.wallet, ..Self::default() };
+    }
+
+    /// The full domain, `name.root`
+    pub fn domain(&self) -> String {
+        format!("{}.{}", self.name, self.root)
+    }
+}
+
+/// A wallet's primary domain, given its primary name record and the name account it points to.
+/// None when the wallet picked none or no longer owns the domain.
+pub fn resolve_primary_name(
+    primary_name: &[u8],
+    name_account: &[u8],
+) -> Result<Option<String>, ProgramError> {
+    let record = PrimaryNameRecord::unpack(primary_name)?;
+    if !record.is_set() {
+        return Ok(None);
+    }
+
+    let name_record = NameRecordHeader::unpack_from_slice(name_account)?;
+    if name_record.owner != record.wallet {
+        return Ok(None);
+    }
+
+    Ok(Some(record.domain()))
+}
+
+/// Derive PDA for a wallet's primary name
+pub fn get_primary_name_key(wallet: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"primary_name", wallet.as_ref()], &crate::ID)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn primary_name_resolves_only_while_owned() {
+        let wallet = Pubkey::new_unique();
+        let mut record = PrimaryNameRecord {
+            wallet,
+            name_account: Pubkey::new_unique(),
+            name: "alice".to_string(),
+            root: "web3".to_string(),
+        };
+        let name_account = |owner| {
+            let mut data = vec![0u8; NameRecordHeader::LEN];
+            NameRecordHeader {
+                parent_name: Pubkey::default(),
+                owner,
+                class: Pubkey::default(),
+                previewer: Pubkey::default(),
+                is_frozen: false,
+                custom_price: 0,
+            }
+            .pack_into_slice(&mut data);
+            data
+        };
+
+        let mut data = vec![0u8; record.space()];
+        record.pack(&mut data);
+        assert_eq!(resolve_primary_name(&data, &name_account(wallet)).unwrap(), Some("alice.web3".to_string()));
+        assert_eq!(resolve_primary_name(&data, &name_account(Pubkey::new_unique())).unwrap(), None);
+
+        record.invalidate();
+        record.pack(&mut data);
+        assert_eq!(resolve_primary_name(&data, &name_account(wallet)).unwrap(), None);
+        assert_eq!(PrimaryNameRecord::unpack(&data).unwrap().wallet, wallet);
+    }
+}
@@ -1,6 +1,7 @@
 use solana_program::program_pack::Pack;
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
+    entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     program_pack::Sealed,
@@ -9,6 +10,29 @@ use solana_program::{
 use web3_domain_name_service::utils::get_seeds_and_key;
 use crate::utils::get_hashed_name;
 
+/// How many different SPL tokens a record can hold profit in
+pub const MAX_PROFIT_MINTS: usize = 4;
+
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// Profit earned in one accepted SPL token -- a slot with nothing left in it is free
+pub struct TokenProfit {
+    pub mint: Pubkey,
+    pub profit: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// What a referrer's downline brought in, lamports only
+pub struct DownlineStats {
+    /// Users who signed up with this record's owner as their referrer
+    pub direct_invitees: u64,
+    /// Domains settled to those invitees
+    pub invitee_domains: u64,
+    /// All profit ever credited
+    pub lifetime_earnings: u64,
+    /// All profit ever withdrawn, the withdrawal fee included
+    pub withdrawn: u64,
+}
+
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
 /// The record for a referrer account — stores earnings and performance
 pub struct ReferrerRecordHeader {
@@ -20,6 +44,15 @@ pub struct ReferrerRecordHeader {
     pub performance: u64,
     /// When this record was created (Unix timestamp, seconds)
     pub create_time: i64,
+    /// Profit earned in SPL tokens, withdrawn in the same token
+    pub token_profits: [TokenProfit; MAX_PROFIT_MINTS],
+    pub downline: DownlineStats,
+    /// When the usr last changed its referrer, 0 if never (Unix timestamp, seconds)
+    pub referrer_changed_at: i64,
+    /// Rank earned through performance, 0 until the first threshold is reached -- never lowered
+    pub tier: u8,
+    /// Who else may withdraw this record's profit, default for nobody
+    pub withdraw_authority: Pubkey,
 }
 
 impl Sealed for ReferrerRecordHeader {}
@@ -31,19 +64,118 @@ impl ReferrerRecordHeader {
             profit: 0,
             performance: 0,
             create_time,
+            token_profits: [TokenProfit::default(); MAX_PROFIT_MINTS],
+            downline: DownlineStats::default(),
+            referrer_changed_at: 0,
+            tier: 0,
+            withdraw_authority: Pubkey::default(),
         }
     }
+
+    /// Add withdrawable lamport profit
+    pub fn add_profit(&mut self, amount: u64) -> Result<(), ProgramError> {
+        self.profit = self.profit
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidArgument)?;
+        self.downline.lifetime_earnings = self.downline.lifetime_earnings
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidArgument)?;
+        Ok(())
+    }
+
+    /// The owner signs for itself, a delegate only if one is set
+    pub fn can_withdraw(&self, owner: &Pubkey, signer: &Pubkey) -> bool {
+        signer == owner
+            || (self.withdraw_authority != Pubkey::default() && signer == &self.withdraw_authority)
+    }
+
+    pub fn token_profit(&self, mint: &Pubkey) -> u64 {
+        self.token_profits
+            .iter()
+            .find(|slot| &slot.mint == mint)
+            .map(|slot| slot.profit)
+            .unwrap_or(0)
+    }
+
+    /// Credit token profit, a new mint takes a slot whose balance was withdrawn
+    pub fn add_token_profit(&mut self, mint: &Pubkey, amount: u64) -> Result<(), ProgramError> {
+        let index = match self.token_profits.iter().position(|slot| &slot.mint == mint) {
+            Some(index) => index,
+            None => self.token_profits
+                .iter()
+                .position(|slot| slot.mint == Pubkey::default() || slot.profit == 0)
+                .ok_or_else(|| {
+                    msg!("no free token profit slot");
+                    ProgramError::InvalidArgument
+                })?,
+        };
+
+        let slot = &mut self.token_profits[index];
+        slot.mint = *mint;
+        slot.profit = slot.profit
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidArgument)?;
+        Ok(())
+    }
+
+    pub fn sub_token_profit(&mut self, mint: &Pubkey, amount: u64) -> Result<(), ProgramError> {
+        let slot = self.token_profits
+            .iter_mut()
+            .find(|slot| &slot.mint == mint)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        slot.profit = slot.profit
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        Ok(())
+    }
 }
 
-impl Pack for ReferrerRecordHeader {
+impl ReferrerRecordHeader {
+    /// Write the record, failing rather than dropping a field an older layout has no room for.
+    /// `pack_into_slice` cuts the newer fields off, a record has to be resized to keep them.
+    pub fn try_pack_into_slice(&self, dst: &mut [u8]) -> ProgramResult {
+        let mut full = vec![0u8; Self::LEN];
+        self.pack_into_slice(&mut full);
+        if dst.len() < Self::LEGACY_LEN || full[dst.len().min(Self::LEN)..].iter().any(|byte| *byte != 0) {
+            msg!("referrer record of {} bytes can't hold all fields, resize it first", dst.len());
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        self.pack_into_slice(dst);
+        Ok(())
+    }
+
     /// 32 (Pubkey) + 8 (profit) + 8 (performance) + 8 (create_time)
-    const LEN: usize = 32 + 8 + 8 + 8;
+    /// -- records created before token profits stop here
+    pub const LEGACY_LEN: usize = 32 + 8 + 8 + 8;
+    /// MAX_PROFIT_MINTS * (32 (mint) + 8 (profit))
+    /// -- records created before downline stats stop after these
+    pub const TOKEN_PROFITS_LEN: usize = MAX_PROFIT_MINTS * (32 + 8);
+    /// 4 * 8 (u64)
+    pub const DOWNLINE_LEN: usize = 4 * 8;
+    /// 8 (referrer_changed_at)
+    pub const REFERRER_CHANGE_LEN: usize = 8;
+    /// 1 (tier)
+    pub const TIER_LEN: usize = 1;
+    /// 32 (withdraw_authority)
+    pub const WITHDRAW_AUTHORITY_LEN: usize = 32;
+}
+
+impl Pack for ReferrerRecordHeader {
+    const LEN: usize = Self::LEGACY_LEN
+        + Self::TOKEN_PROFITS_LEN
+        + Self::DOWNLINE_LEN
+        + Self::REFERRER_CHANGE_LEN
+        + Self::TIER_LEN
+        + Self::WITHDRAW_AUTHORITY_LEN;
 
+    // an older record only gets the fields it has room for written,
+    // it has to be resized before it can hold the newer ones
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        if dst.len() < Self::LEN {
+        if dst.len() < Self::LEGACY_LEN {
             msg!("Invalid destination slice length for ReferrerRecordHeader");
             return;
         }
+        let (dst, tail_dst) = dst.split_at_mut(Self::LEGACY_LEN);
 
         let (pubkey_dst, rest) = dst.split_at_mut(32);
         pubkey_dst.copy_from_slice(self.referrer_account.as_ref());
@@ -55,14 +187,26 @@ impl Pack for ReferrerRecordHeader {
         performance_dst.copy_from_slice(&self.performance.to_le_bytes());
 
         create_time_dst.copy_from_slice(&self.create_time.to_le_bytes());
+
+        // fields added after the legacy layout, cut off where an older record ends
+        let mut tail = Vec::with_capacity(Self::LEN - Self::LEGACY_LEN);
+        self.token_profits.serialize(&mut tail).unwrap();
+        self.downline.serialize(&mut tail).unwrap();
+        self.referrer_changed_at.serialize(&mut tail).unwrap();
+        self.tier.serialize(&mut tail).unwrap();
+        self.withdraw_authority.serialize(&mut tail).unwrap();
+
+        let len = tail.len().min(tail_dst.len());
+        tail_dst[..len].copy_from_slice(&tail[..len]);
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        if src.len() < Self::LEN {
+        if src.len() < Self::LEGACY_LEN {
             msg!("Invalid data length for ReferrerRecordHeader");
             return Err(ProgramError::InvalidAccountData);
         }
 
+        let (src, tail_src) = src.split_at(Self::LEGACY_LEN);
         let (pubkey_src, rest) = src.split_at(32);
         let (profit_src, rest) = rest.split_at(8);
         let (performance_src, create_time_src) = rest.split_at(8);
@@ -87,11 +231,33 @@ impl Pack for ReferrerRecordHeader {
                 .map_err(|_| ProgramError::InvalidAccountData)?,
         );
 
+        // fields an older record doesn't have read as zero
+        let mut tail = [0u8; Self::LEN - Self::LEGACY_LEN];
+        let len = tail_src.len().min(tail.len());
+        tail[..len].copy_from_slice(&tail_src[..len]);
+
+        let mut p: &[u8] = &tail;
+        let token_profits = <[TokenProfit; MAX_PROFIT_MINTS]>::deserialize(&mut p)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let downline = DownlineStats::deserialize(&mut p)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let referrer_changed_at = i64::deserialize(&mut p)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let tier = u8::deserialize(&mut p)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        let withdraw_authority = Pubkey::deserialize(&mut p)
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+
         Ok(Self {
             referrer_account,
             profit,
             performance,
             create_time,
+            token_profits,
+            downline,
+            referrer_changed_at,
+            tier,
+            withdraw_authority,
         })
     }
 }
@@ -105,3 +271,61 @@ pub fn get_referrer_record_key(usr: &Pubkey) -> (Pubkey, Vec<u8>) {
         Some(&crate::ID),
     )
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn older_layouts_unpack_and_keep_their_fields() {
+        let mut record = ReferrerRecordHeader::new(Pubkey::new_unique(), 7);
+        record.add_profit(100).unwrap();
+        record.add_token_profit(&Pubkey::new_unique(), 5).unwrap();
+        record.downline.direct_invitees = 2;
+
+        let mut full = vec![0u8; ReferrerRecordHeader::LEN];
+        record.pack_into_slice(&mut full);
+        assert_eq!(ReferrerRecordHeader::unpack_from_slice(&full).unwrap(), record);
+
+        let legacy = &full[..ReferrerRecordHeader::LEGACY_LEN];
+        let unpacked = ReferrerRecordHeader::unpack_from_slice(legacy).unwrap();
+        assert_eq!(unpacked.profit, 100);
+        assert_eq!(unpacked.token_profits, [TokenProfit::default(); MAX_PROFIT_MINTS]);
+        assert_eq!(unpacked.downline, DownlineStats::default());
+
+        let with_tokens = &full[..ReferrerRecordHeader::LEGACY_LEN + ReferrerRecordHeader::TOKEN_PROFITS_LEN];
+        let unpacked = ReferrerRecordHeader::unpack_from_slice(with_tokens).unwrap();
+        assert_eq!(unpacked.token_profits, record.token_profits);
+        assert_eq!(unpacked.downline, DownlineStats::default());
+    }
+    #[test]
+    fn an_older_layout_refuses_fields_it_cant_hold() {
+        let mut record = ReferrerRecordHeader::new(Pubkey::new_unique(), 7);
+        record.profit = 100;
+
+        let mut legacy = vec![0u8; ReferrerRecordHeader::LEGACY_LEN];
+        record.try_pack_into_slice(&mut legacy).unwrap();
+
+        record.add_token_profit(&Pubkey::new_unique(), 5).unwrap();
+        assert_eq!(record.try_pack_into_slice(&mut legacy), Err(ProgramError::AccountDataTooSmall));
+
+        let mut full = vec![0u8; ReferrerRecordHeader::LEN];
+        record.try_pack_into_slice(&mut full).unwrap();
+        assert_eq!(ReferrerRecordHeader::unpack_from_slice(&full).unwrap(), record);
+    }
+
+    #[test]
+    fn a_withdrawn_token_frees_its_slot() {
+        let mut record = ReferrerRecordHeader::new(Pubkey::new_unique(), 7);
+        let mints: Vec<Pubkey> = (0..=MAX_PROFIT_MINTS).map(|_| Pubkey::new_unique()).collect();
+        for mint in &mints[..MAX_PROFIT_MINTS] {
+            record.add_token_profit(mint, 10).unwrap();
+        }
+        assert!(record.add_token_profit(&mints[MAX_PROFIT_MINTS], 10).is_err());
+
+        record.sub_token_profit(&mints[1], 10).unwrap();
+        record.add_token_profit(&mints[MAX_PROFIT_MINTS], 10).unwrap();
+        assert_eq!(record.token_profit(&mints[MAX_PROFIT_MINTS]), 10);
+        assert_eq!(record.token_profit(&mints[1]), 0);
+    }
+}
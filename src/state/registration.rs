@@ -0,0 +1,71 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+use web3_domain_name_service::utils::get_seeds_and_key;
+
+use crate::utils::get_hashed_name;
+
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// The term of a settled domain -- one per name account
+pub struct RegistrationRecordHeader {
+    /// The owner the current term belongs to,
+    /// `Pubkey::default()` while a lapsed name is being re-auctioned
+    pub owner: Pubkey,
+    /// When the current term ends (Unix timestamp, seconds)
+    pub expire_time: i64,
+}
+
+impl Sealed for RegistrationRecordHeader {}
+
+impl RegistrationRecordHeader {
+    pub fn new(owner: Pubkey, expire_time: i64) -> Self {
+        Self { owner, expire_time }
+    }
+
+    /// The grace period is over and the name can be re-auctioned
+    pub fn is_lapsed(&self, grace_period: i64, now: i64) -> bool {
+        now > self.expire_time.saturating_add(grace_period)
+    }
+
+    /// The registrar took the name back and it is waiting for a new owner
+    pub fn is_reclaimed(&self) -> bool {
+        self.owner == Pubkey::default()
+    }
+}
+
+impl Pack for RegistrationRecordHeader {
+    /// 32 (owner) + 8 (expire_time)
+    const LEN: usize = 32 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        RegistrationRecordHeader::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize registration record");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+/// Derive PDA for a domain's registration record
+pub fn get_registration_key(
+    domain_sub_name: &str,
+    root_domain_key: &Pubkey,
+) -> (Pubkey, Vec<u8>) {
+    get_seeds_and_key(
+        &crate::ID,
+        get_hashed_name(domain_sub_name),
+        Some(&crate::ID),
+        Some(root_domain_key),
+    )
+}
@@ -0,0 +1,151 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+
+/// Days the daily buckets cover, the oldest is reused after that
+pub const DAILY_BUCKETS: usize = 30;
+/// Seconds in a bucket
+pub const SECONDS_PER_DAY: i64 = 86400;
+
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// Activity during one UTC day
+pub struct DailyStats {
+    /// Days since the unix epoch, the bucket is unused while 0
+    pub day: i64,
+    /// Lamport settlement prices (lamports)
+    pub settled_volume: u64,
+    /// Auctions settled, token auctions included
+    pub settlements: u64,
+    /// Settlements that sold a domain on from its previous owner
+    pub resales: u64,
+    /// Auctions opened
+    pub auctions_opened: u64,
+    /// Renewal, subdomain and withdrawal fees (lamports)
+    pub fees: u64,
+    /// Lamport profit withdrawn from the vault, fees included (lamports)
+    pub withdrawn: u64,
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// Protocol-wide totals, with the last `DAILY_BUCKETS` days kept apart
+pub struct StatsRecord {
+    /// Lamport settlement prices ever (lamports)
+    pub settled_volume: u64,
+    /// Auctions ever settled
+    pub settlements: u64,
+    /// Settlements that were resales
+    pub resales: u64,
+    /// Renewal, subdomain and withdrawal fees ever (lamports)
+    pub fees: u64,
+    /// Auctions opened and not yet settled
+    pub active_auctions: u64,
+    /// Root domains whose crowdfunding completed
+    pub roots_activated: u64,
+    /// Withdrawals of lamport profit
+    pub withdrawals: u64,
+    /// Lamport profit ever withdrawn, fees included (lamports)
+    pub withdrawn: u64,
+    /// Daily buckets, the one for day `d` sits in slot `d % DAILY_BUCKETS`
+    pub days: [DailyStats; DAILY_BUCKETS],
+}
+
+impl Sealed for StatsRecord {}
+
+impl Default for StatsRecord {
+    fn default() -> Self {
+        Self {
+            settled_volume: 0,
+            settlements: 0,
+            resales: 0,
+            fees: 0,
+            active_auctions: 0,
+            roots_activated: 0,
+            withdrawals: 0,
+            withdrawn: 0,
+            days: [DailyStats::default(); DAILY_BUCKETS],
+        }
+    }
+}
+
+impl Pack for StatsRecord {
+    // 8 * u64 (8) + DAILY_BUCKETS * (i64 (8) + 6 * u64 (8))
+    const LEN: usize = 8 * 8 + DAILY_BUCKETS * (8 + 6 * 8);
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        StatsRecord::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize StatsRecord");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl StatsRecord {
+    /// The bucket for the day `now` falls in, cleared if it still holds an older day
+    pub fn today(&mut self, now: i64) -> &mut DailyStats {
+        let day = now.div_euclid(SECONDS_PER_DAY);
+        let bucket = &mut self.days[day.rem_euclid(DAILY_BUCKETS as i64) as usize];
+        if bucket.day != day {
+            *bucket = DailyStats { day, ..DailyStats::default() };
+        }
+        bucket
+    }
+
+    /// The buckets of the last `DAILY_BUCKETS` days up to `now`, oldest first --
+    /// days without activity read as empty buckets
+    pub fn recent_days(&self, now: i64) -> Vec<DailyStats> {
+        let today = now.div_euclid(SECONDS_PER_DAY);
+        (today - DAILY_BUCKETS as i64 + 1..=today)
+            .map(|day| {
+                let bucket = self.days[day.rem_euclid(DAILY_BUCKETS as i64) as usize];
+                if bucket.day == day {
+                    bucket
+                } else {
+                    DailyStats { day, ..DailyStats::default() }
+                }
+            })
+            .collect()
+    }
+}
+
+/// Derive PDA for the protocol statistics
+pub fn get_stats_key() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"stats"], &crate::ID)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn daily_buckets_wrap_and_reset() {
+        let mut stats = StatsRecord::default();
+        let day = |d: i64| d * SECONDS_PER_DAY + 10;
+
+        stats.today(day(100)).settlements += 1;
+        stats.today(day(100)).settlements += 1;
+        stats.today(day(101)).fees += 7;
+        // the same slot a ring later starts over
+        stats.today(day(100 + DAILY_BUCKETS as i64)).settlements += 1;
+
+        let recent = stats.recent_days(day(100 + DAILY_BUCKETS as i64));
+        assert_eq!(recent.len(), DAILY_BUCKETS);
+        assert_eq!((recent[0].day, recent[0].fees), (101, 7));
+        assert_eq!(recent[DAILY_BUCKETS - 1].settlements, 1);
+        assert!(recent[1..DAILY_BUCKETS - 1].iter().all(|d| d.settlements == 0 && d.fees == 0));
+
+        let mut data = vec![0u8; StatsRecord::LEN];
+        stats.pack_into_slice(&mut data);
+        assert_eq!(StatsRecord::unpack_from_slice(&data).unwrap(), stats);
+    }
+}
@@ -0,0 +1,40 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// What the owner of a domain charges for every subdomain issued under it
+pub struct SubdomainFeeRecord {
+    /// Fee paid by the subdomain's payer, credited to the parent owner's profit (lamports)
+    pub fee: u64,
+}
+
+impl Sealed for SubdomainFeeRecord {}
+
+impl Pack for SubdomainFeeRecord {
+    const LEN: usize = 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        SubdomainFeeRecord::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize subdomain fee record");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+/// Derive PDA for the subdomain fee of a parent domain
+pub fn get_subdomain_fee_key(parent_domain: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"subdomain_fee", parent_domain.as_ref()], &crate::ID)
+}
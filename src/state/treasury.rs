@@ -0,0 +1,44 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// What a treasury recipient has received from admin withdrawals
+pub struct TreasuryPayoutRecord {
+    pub recipient: Pubkey,
+    /// Lamports paid to the recipient so far
+    pub paid: u64,
+    /// Unix timestamp of the latest payout, seconds
+    pub last_paid_at: i64,
+}
+
+impl Sealed for TreasuryPayoutRecord {}
+
+impl Pack for TreasuryPayoutRecord {
+    // Pubkey (32) + u64 (8) + i64 (8)
+    const LEN: usize = 32 + 8 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        TreasuryPayoutRecord::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize treasury payout record");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+/// Derive PDA for a treasury recipient's payout record
+pub fn get_treasury_payout_key(recipient: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"treasury_payout", recipient.as_ref()], &crate::ID)
+}
@@ -1,12 +1,26 @@
 use solana_program::program_pack::Pack;
 use borsh::{BorshDeserialize, BorshSerialize};
 use solana_program::{
+    entrypoint::ProgramResult,
     msg,
     program_error::ProgramError,
     program_pack::Sealed,
     pubkey::Pubkey,
 };
 
+/// Domains the vault record ranks
+pub const TOP_DOMAINS: usize = 6;
+
+/// How many different SPL tokens the vault books revenue in
+pub const MAX_REVENUE_MINTS: usize = 8;
+
+#[derive(Clone, Copy, Debug, Default, BorshSerialize, BorshDeserialize, PartialEq)]
+/// Revenue earned in one accepted SPL token -- a default mint marks a free slot
+pub struct TokenRevenue {
+    pub mint: Pubkey,
+    /// Base units the protocol earned and hasn't withdrawn yet
+    pub revenue: u64,
+}
 
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
 pub struct ValuableDomain {
@@ -25,7 +39,16 @@ pub struct VaultRecord {
     /// Current number of valid high-value domains
     pub top_len: u8,
     /// Top 6 most valuable domains
-    pub top_domains: [ValuableDomain; 6],
+    pub top_domains: [ValuableDomain; TOP_DOMAINS],
+    /// Lamports the vault owes: unwithdrawn profit, open bids and unfinished root crowdfunding.
+    /// Starts at 0 on a vault migrated from an older version, so it reads low
+    /// until everything owed from before the upgrade has been paid out.
+    pub liabilities: u64,
+    /// Lamports the protocol earned and hasn't withdrawn yet
+    pub revenue: u64,
+    /// Revenue per SPL token, kept in the vault's token accounts. A slot stays with its mint
+    /// once taken, `SetAcceptedMint` takes it before the first auction in that token.
+    pub token_revenue: [TokenRevenue; MAX_REVENUE_MINTS],
 }
 
 impl Sealed for VaultRecord {}
@@ -40,47 +63,203 @@ impl VaultRecord {
                 domain: Pubkey::default(),
                 value: 0,
             }),
+            liabilities: 0,
+            revenue: 0,
+            token_revenue: [TokenRevenue::default(); MAX_REVENUE_MINTS],
         }
     }
 
-    /// Update top domains if new domain value is higher than the lowest
+    /// The vault took in lamports that belong to users
+    pub fn owe(&mut self, amount: u64) -> Result<(), ProgramError> {
+        self.liabilities = self.liabilities
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidArgument)?;
+        Ok(())
+    }
+
+    /// Lamports owed to users were paid out or turned into revenue.
+    /// Saturates: what was owed before the vault tracked it was never counted.
+    pub fn settle_owed(&mut self, amount: u64) {
+        self.liabilities = self.liabilities.saturating_sub(amount);
+    }
+
+    /// The protocol earned lamports
+    pub fn earn(&mut self, amount: u64) -> Result<(), ProgramError> {
+        self.revenue = self.revenue
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidArgument)?;
+        Ok(())
+    }
+
+    /// The slot booking a mint's revenue, taking a free one for a new mint
+    pub fn token_slot(&mut self, mint: &Pubkey) -> Result<&mut TokenRevenue, ProgramError> {
+        let index = match self.token_revenue.iter().position(|slot| &slot.mint == mint) {
+            Some(index) => index,
+            None => self.token_revenue
+                .iter()
+                .position(|slot| slot.mint == Pubkey::default())
+                .ok_or_else(|| {
+                    msg!("the vault books revenue in {} tokens at most", MAX_REVENUE_MINTS);
+                    ProgramError::InvalidArgument
+                })?,
+        };
+        let slot = &mut self.token_revenue[index];
+        slot.mint = *mint;
+        Ok(slot)
+    }
+
+    /// The protocol earned tokens
+    pub fn earn_token(&mut self, mint: &Pubkey, amount: u64) -> Result<(), ProgramError> {
+        let slot = self.token_slot(mint)?;
+        slot.revenue = slot.revenue
+            .checked_add(amount)
+            .ok_or(ProgramError::InvalidArgument)?;
+        Ok(())
+    }
+
+    /// Token revenue the admins took out
+    pub fn spend_token_revenue(&mut self, mint: &Pubkey, amount: u64) -> Result<(), ProgramError> {
+        let slot = self.token_revenue
+            .iter_mut()
+            .find(|slot| &slot.mint == mint)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        slot.revenue = slot.revenue
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        Ok(())
+    }
+
+    /// Revenue the admins can take without touching what users are owed or the rent
+    pub fn free_balance(&self, vault_lamports: u64, rent_exempt: u64) -> u64 {
+        let spare = vault_lamports
+            .saturating_sub(rent_exempt)
+            .saturating_sub(self.liabilities);
+        self.revenue.min(spare)
+    }
+
+    /// Rank a domain at its latest price, highest first with one entry per domain.
+    /// The full board lives in the domain leaderboard, this keeps the top 6.
     pub fn update_top_domain(&mut self, domain: Pubkey, value: u64) {
-        // If not at capacity, add the domain
-        if self.top_len < 6 {
-            self.top_domains[self.top_len as usize] = ValuableDomain { domain, value };
-            self.top_len += 1;
-            return;
+        let len = (self.top_len as usize).min(TOP_DOMAINS);
+        let mut top: Vec<ValuableDomain> = self.top_domains[..len]
+            .iter()
+            .filter(|held| held.domain != domain)
+            .cloned()
+            .collect();
+        // vaults written before the board was sorted
+        top.sort_by_key(|held| core::cmp::Reverse(held.value));
+
+        let rank = top.partition_point(|held| held.value >= value);
+        top.insert(rank, ValuableDomain { domain, value });
+        top.truncate(TOP_DOMAINS);
+
+        self.top_len = top.len() as u8;
+        for (i, slot) in self.top_domains.iter_mut().enumerate() {
+            *slot = top.get(i).cloned().unwrap_or(ValuableDomain {
+                domain: Pubkey::default(),
+                value: 0,
+            });
         }
+    }
+}
 
-        // Find the minimum value in the top domains
-        let min_index = self.top_domains
-            .iter()
-            .enumerate()
-            .min_by_key(|(_, d)| d.value)
-            .map(|(i, _)| i)
-            .unwrap_or(0);
-
-        // Replace if new value is higher than the minimum
-        if value > self.top_domains[min_index].value {
-            self.top_domains[min_index] = ValuableDomain { domain, value };
+impl VaultRecord {
+    // u32 (4) + u32 (4) + u8 (1) + 6 * (32 + 8) = 4 + 4 + 1 + 240 = 249
+    // -- vaults created before the solvency accounting stop here
+    pub const LEGACY_LEN: usize = 4 + 4 + 1 + (6 * (32 + 8));
+    /// LEGACY_LEN + u64 (8) + u64 (8) -- vaults created before token revenue stop here
+    pub const SOLVENCY_LEN: usize = Self::LEGACY_LEN + 8 + 8;
+
+    /// Pack into an account that must hold every field set, fails rather than drop one
+    pub fn try_pack_into_slice(&self, dst: &mut [u8]) -> ProgramResult {
+        let mut full = vec![0u8; Self::LEN];
+        self.pack_into_slice(&mut full);
+        if dst.len() < Self::LEGACY_LEN || full[dst.len().min(Self::LEN)..].iter().any(|byte| *byte != 0) {
+            msg!("vault of {} bytes can't hold all fields, resize it first", dst.len());
+            return Err(ProgramError::AccountDataTooSmall);
         }
+        self.pack_into_slice(dst);
+        Ok(())
     }
 }
 
 impl Pack for VaultRecord {
-    // u32 (4) + u32 (4) + u8 (1) + 6 * (32 + 8) = 4 + 4 + 1 + 240 = 249
-    const LEN: usize = 4 + 4 + 1 + (6 * (32 + 8));
+    // SOLVENCY_LEN + MAX_REVENUE_MINTS * (32 (mint) + 8 (revenue))
+    const LEN: usize = Self::SOLVENCY_LEN + MAX_REVENUE_MINTS * (32 + 8);
 
+    // a legacy vault only gets the fields it has room for written
     fn pack_into_slice(&self, dst: &mut [u8]) {
-        let mut slice = dst;
-        self.serialize(&mut slice).unwrap()
+        let data = self.try_to_vec().unwrap();
+        let len = data.len().min(dst.len());
+        dst[..len].copy_from_slice(&data[..len]);
     }
 
     fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
-        let mut p = src;
+        if src.len() < Self::LEGACY_LEN {
+            msg!("Invalid data length for VaultRecord");
+            return Err(ProgramError::InvalidAccountData);
+        }
+        let mut buf = [0u8; Self::LEN];
+        let len = src.len().min(Self::LEN);
+        buf[..len].copy_from_slice(&src[..len]);
+
+        let mut p: &[u8] = &buf;
         VaultRecord::deserialize(&mut p).map_err(|_| {
             msg!("Failed to deserialize VaultRecord");
             ProgramError::InvalidAccountData
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn legacy_vault_unpacks_and_only_revenue_is_free() {
+        let mut vault = VaultRecord::new();
+        vault.usr_count = 3;
+        vault.owe(700).unwrap();
+        vault.earn(200).unwrap();
+
+        let mut full = vec![0u8; VaultRecord::LEN];
+        vault.pack_into_slice(&mut full);
+        assert_eq!(VaultRecord::unpack_from_slice(&full).unwrap(), vault);
+
+        let legacy = VaultRecord::unpack_from_slice(&full[..VaultRecord::LEGACY_LEN]).unwrap();
+        assert_eq!(legacy.usr_count, 3);
+        assert_eq!((legacy.liabilities, legacy.revenue), (0, 0));
+
+        // a legacy vault can't take the books without growing
+        assert!(vault.try_pack_into_slice(&mut full[..VaultRecord::LEGACY_LEN]).is_err());
+        assert!(legacy.try_pack_into_slice(&mut full[..VaultRecord::LEGACY_LEN]).is_ok());
+
+        // 1000 lamports, 50 of rent: 250 spare after liabilities, but only 200 earned
+        assert_eq!(vault.free_balance(1_000, 50), 200);
+        // a shortfall leaves nothing free
+        assert_eq!(vault.free_balance(600, 50), 0);
+    }
+
+    #[test]
+    fn token_revenue_is_booked_per_mint() {
+        let mut vault = VaultRecord::new();
+        let mints: Vec<Pubkey> = (0..=MAX_REVENUE_MINTS).map(|_| Pubkey::new_unique()).collect();
+        vault.earn_token(&mints[0], 50).unwrap();
+        vault.earn_token(&mints[0], 25).unwrap();
+        assert!(vault.spend_token_revenue(&mints[0], 76).is_err());
+        vault.spend_token_revenue(&mints[0], 75).unwrap();
+        assert!(vault.spend_token_revenue(&mints[1], 1).is_err());
+
+        // a slot stays with its mint at a zero balance
+        for mint in &mints[1..MAX_REVENUE_MINTS] {
+            vault.earn_token(mint, 1).unwrap();
+        }
+        assert!(vault.earn_token(&mints[MAX_REVENUE_MINTS], 1).is_err());
+
+        let mut full = vec![0u8; VaultRecord::LEN];
+        vault.pack_into_slice(&mut full);
+        assert_eq!(VaultRecord::unpack_from_slice(&full).unwrap(), vault);
+        let older = VaultRecord::unpack_from_slice(&full[..VaultRecord::SOLVENCY_LEN]).unwrap();
+        assert_eq!(older.token_revenue, [TokenRevenue::default(); MAX_REVENUE_MINTS]);
+    }
+}
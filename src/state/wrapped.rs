@@ -0,0 +1,98 @@
+use solana_program::program_pack::Pack;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    msg,
+    program_error::ProgramError,
+    program_pack::Sealed,
+    pubkey::Pubkey,
+};
+use spl_token::state::Account as TokenAccount;
+
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize, PartialEq)]
+/// A domain held by the registrar for the supply-1 token standing in for it.
+/// The record owns the name while it is wrapped, and is closed when the name is sold at auction.
+/// Records left by sales before that are stale and reused by the next wrap.
+pub struct WrappedNameRecord {
+    /// The domain name account
+    pub name_account: Pubkey,
+    /// The token of the current wrap -- tokens of earlier wraps are worth nothing
+    pub mint: Pubkey,
+    /// When the domain was wrapped (Unix timestamp, seconds)
+    pub wrapped_at: i64,
+}
+
+impl Sealed for WrappedNameRecord {}
+
+impl Pack for WrappedNameRecord {
+    /// 32 (name_account) + 32 (mint) + 8 (wrapped_at)
+    const LEN: usize = 32 + 32 + 8;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        let mut slice = dst;
+        self.serialize(&mut slice).unwrap()
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> Result<Self, ProgramError> {
+        let mut p = src;
+        WrappedNameRecord::deserialize(&mut p).map_err(|_| {
+            msg!("Failed to deserialize wrapped name");
+            ProgramError::InvalidAccountData
+        })
+    }
+}
+
+impl WrappedNameRecord {
+    /// The wallet a token account shows holding the domain
+    pub fn holder(&self, token_account: &[u8]) -> Result<Pubkey, ProgramError> {
+        let token_account = TokenAccount::unpack(token_account)?;
+        if token_account.mint != self.mint || token_account.amount != 1 {
+            msg!("token account doesn't hold {}", self.mint);
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        Ok(token_account.owner)
+    }
+}
+
+/// The registrar PDA owning a wrapped domain
+pub fn get_wrapped_name_key(name_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[b"wrapped_name", name_account.as_ref()], &crate::ID)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use spl_token::state::AccountState;
+
+    #[test]
+    fn only_the_current_token_holds_the_domain() {
+        let record = WrappedNameRecord {
+            name_account: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            wrapped_at: 1_700_000_000,
+        };
+        let mut data = [0u8; WrappedNameRecord::LEN];
+        record.pack_into_slice(&mut data);
+        assert_eq!(WrappedNameRecord::unpack_from_slice(&data).unwrap(), record);
+
+        let holder = Pubkey::new_unique();
+        let token_account = |mint, amount| {
+            let mut data = vec![0u8; TokenAccount::LEN];
+            TokenAccount {
+                mint,
+                owner: holder,
+                amount,
+                state: AccountState::Initialized,
+                ..TokenAccount::default()
+            }
+            .pack_into_slice(&mut data);
+            data
+        };
+
+        assert_eq!(record.holder(&token_account(record.mint, 1)).unwrap(), holder);
+        assert!(record.holder(&token_account(record.mint, 0)).is_err());
+        // a token left over from an earlier wrap
+        assert!(record.holder(&token_account(Pubkey::new_unique(), 1)).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+//! Keep each root's index of live auctions
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
+};
+use web3_utils::check::{check_account_key, check_account_owner};
+
+use crate::{
+    state::{
+        NameStateRecordHeader,
+        auction_index::{
+            AuctionEntry, AuctionIndexHeader, AuctionPage, AuctionPageHeader,
+            get_auction_index_key, get_auction_page_key,
+        },
+    },
+    utils::{create_pda_account, resize_pda_account},
+};
+
+/// The index entry for an auction state
+pub fn auction_entry(name_state: Pubkey, state: &NameStateRecordHeader) -> Result<AuctionEntry, ProgramError> {
+    Ok(AuctionEntry {
+        name_state,
+        price: state.highest_price,
+        end_time: state.end_time,
+    })
+}
+
+/// List a new auction on the page the caller picked: a page with room, or the next one,
+/// which is created. Pages grow one entry at a time, the payer covers the rent.
+/// Returns the page number, for the state to record.
+pub fn index_auction<'a>(
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    root: &Pubkey,
+    index: &AccountInfo<'a>,
+    page: &AccountInfo<'a>,
+    entry: AuctionEntry,
+) -> Result<u32, ProgramError> {
+
+    let (index_key, index_bump) = get_auction_index_key(root);
+    check_account_key(index, &index_key)?;
+    let mut index_data = if index.data_is_empty() {
+        create_pda_account(
+            payer,
+            index,
+            system_program,
+            AuctionIndexHeader::LEN,
+            &[b"auction_index", root.as_ref(), &[index_bump]],
+        )?;
+        AuctionIndexHeader { root: *root, pages: 0, live: 0 }
+    } else {
+        check_account_owner(index, &crate::ID)?;
+        AuctionIndexHeader::unpack_from_slice(&index.data.borrow())?
+    };
+
+    let mut page_data = if page.data_is_empty() {
+        // only the next page can be opened, so pages stay numbered without gaps
+        let number = index_data.pages;
+        let (page_key, page_bump) = get_auction_page_key(root, number);
+        if page.key != &page_key {
+            msg!("the next page to open is {}: {}", number, page_key);
+            return Err(ProgramError::InvalidArgument);
+        }
+        create_pda_account(
+            payer,
+            page,
+            system_program,
+            AuctionPageHeader::space(0),
+            &[b"auction_index", root.as_ref(), &number.to_le_bytes(), &[page_bump]],
+        )?;
+        index_data.pages = index_data.pages.checked_add(1).ok_or(ProgramError::InvalidArgument)?;
+        msg!("open auction page {}", number);
+
+        AuctionPage {
+            header: AuctionPageHeader { root: *root, page: number, len: 0 },
+            entries: Vec::new(),
+        }
+    } else {
+        load_page(root, page)?
+    };
+
+    if page_data.position(&entry.name_state).is_some() {
+        msg!("auction already listed");
+        return Err(ProgramError::InvalidArgument);
+    }
+    if page_data.is_full() {
+        msg!("auction page {} is full", page_data.header.page);
+        return Err(ProgramError::InvalidArgument);
+    }
+    page_data.entries.push(entry);
+
+    resize_pda_account(payer, page, system_program, AuctionPageHeader::space(page_data.entries.len()))?;
+    page_data.pack(&mut page.data.borrow_mut());
+
+    index_data.live = index_data.live.checked_add(1).ok_or(ProgramError::InvalidArgument)?;
+    index_data.pack_into_slice(&mut index.data.borrow_mut());
+
+    Ok(page_data.header.page)
+}
+
+/// Refresh a listed auction's price and end time on the page its state records
+pub fn update_indexed_auction(
+    root: &Pubkey,
+    page: &AccountInfo,
+    auction_page: Option<u32>,
+    entry: AuctionEntry,
+) -> ProgramResult {
+
+    let Some((mut page_data, i)) = find_listing(root, page, auction_page, &entry.name_state)? else {
+        return Ok(());
+    };
+    page_data.entries[i] = entry;
+    page_data.pack(&mut page.data.borrow_mut());
+
+    Ok(())
+}
+
+/// Take a settled auction off the page its state records
+pub fn unindex_auction(
+    root: &Pubkey,
+    index: &AccountInfo,
+    page: &AccountInfo,
+    auction_page: Option<u32>,
+    name_state: &Pubkey,
+) -> ProgramResult {
+
+    check_account_key(index, &get_auction_index_key(root).0)?;
+    let Some((mut page_data, i)) = find_listing(root, page, auction_page, name_state)? else {
+        return Ok(());
+    };
+    page_data.entries.swap_remove(i);
+    page_data.pack(&mut page.data.borrow_mut());
+
+    check_account_owner(index, &crate::ID)?;
+    let mut index_data = AuctionIndexHeader::unpack_from_slice(&index.data.borrow())?;
+    index_data.live = index_data.live.saturating_sub(1);
+    index_data.pack_into_slice(&mut index.data.borrow_mut());
+
+    Ok(())
+}
+
+/// Check the page passed for an auction without writing it, as `unindex_auction` will
+pub fn check_listing(
+    root: &Pubkey,
+    page: &AccountInfo,
+    auction_page: Option<u32>,
+    name_state: &Pubkey,
+) -> ProgramResult {
+    find_listing(root, page, auction_page, name_state).map(|_| ())
+}
+
+/// Find an auction on the page its state records, which must list it.
+/// A state that records no page was opened before pages were recorded: it is looked up
+/// on the page given, and one opened before the index isn't on any.
+fn find_listing(
+    root: &Pubkey,
+    page: &AccountInfo,
+    auction_page: Option<u32>,
+    name_state: &Pubkey,
+) -> Result<Option<(AuctionPage, usize)>, ProgramError> {
+
+    let Some(number) = auction_page else {
+        if page.data_is_empty() {
+            msg!("auction not indexed");
+            return Ok(None);
+        }
+        let page_data = load_page(root, page)?;
+        let Some(i) = page_data.position(name_state) else {
+            msg!("auction not on page {}", page_data.header.page);
+            return Ok(None);
+        };
+        return Ok(Some((page_data, i)));
+    };
+
+    check_account_key(page, &get_auction_page_key(root, number).0)?;
+    let page_data = load_page(root, page)?;
+    let i = page_data.position(name_state).ok_or_else(|| {
+        msg!("auction not on its page {}", number);
+        ProgramError::InvalidArgument
+    })?;
+    Ok(Some((page_data, i)))
+}
+
+fn load_page(root: &Pubkey, page: &AccountInfo) -> Result<AuctionPage, ProgramError> {
+    check_account_owner(page, &crate::ID)?;
+    let page_data = AuctionPage::unpack(&page.data.borrow())?;
+    check_account_key(page, &get_auction_page_key(root, page_data.header.page).0)?;
+    Ok(page_data)
+}
@@ -0,0 +1,139 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::set_return_data, program_error::ProgramError
+};
+
+use crate::processor::{finalize_name, start_name};
+
+/// Accounts a versioned transaction can address, its lookup tables included.
+/// The cluster's account lock limit still bounds each transaction, so a keeper
+/// fills a batch up to whichever comes first.
+pub const MAX_TX_ACCOUNTS: usize = 256;
+
+/// Unused accounts of an item, such as the previous owner's on a first sale,
+/// can repeat a shared account, so an item is sized by the accounts it needs of its own.
+pub const MAX_BATCH_START: usize = (MAX_TX_ACCOUNTS - start_name::Accounts::SHARED.len())
+    / (start_name::Accounts::FIXED_LEN - start_name::Accounts::SHARED.len());
+
+/// Referrers' records are left out, winners' chains mostly meet at the same referrers
+pub const MAX_BATCH_FINALIZE: usize = (MAX_TX_ACCOUNTS - finalize_name::Accounts::SHARED.len())
+    / (finalize_name::Accounts::FIXED_LEN - finalize_name::Accounts::SHARED.len());
+
+const _: () = assert!(MAX_BATCH_START >= 1 && MAX_BATCH_FINALIZE >= 1);
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+/// Outcome of one batch item, returned in item order
+pub enum BatchItemStatus {
+    Done,
+    /// The item failed validation and nothing was written -- carries the `ProgramError` code
+    Skipped(u64),
+}
+
+/// Cut the account group of the next item off the instruction accounts
+pub fn next_account_group<'a, 'b>(
+    accounts: &'a [AccountInfo<'b>],
+    offset: &mut usize,
+    len: u8,
+) -> Result<&'a [AccountInfo<'b>], ProgramError> {
+    let end = offset.saturating_add(len as usize);
+    let group = accounts.get(*offset..end).ok_or_else(|| {
+        msg!("account group out of range: {}..{}", offset, end);
+        ProgramError::NotEnoughAccountKeys
+    })?;
+    *offset = end;
+    Ok(group)
+}
+
+/// Lay an item's accounts out like the single instruction takes them: the shared accounts
+/// fill `shared_positions`, the item's own accounts fill the other slots in order and then follow.
+/// A group too short to fill the slots comes out short, for `parse` to refuse.
+pub fn expand_item_accounts<'b>(
+    shared: &[AccountInfo<'b>],
+    shared_positions: &[usize],
+    group: &[AccountInfo<'b>],
+) -> Vec<AccountInfo<'b>> {
+    let mut shared = shared.iter();
+    let mut own = group.iter();
+    (0..)
+        .map_while(|position| {
+            if shared_positions.contains(&position) { shared.next() } else { own.next() }
+        })
+        .cloned()
+        .collect()
+}
+
+/// Apply an item whose validation passed, skip one whose validation failed.
+/// Validation writes nothing, so a skipped item leaves no trace; a failure once the item
+/// is being applied may follow writes and fails the whole batch.
+pub fn run_batch_item<P>(
+    index: usize,
+    prepared: Result<P, ProgramError>,
+    apply: impl FnOnce(P) -> ProgramResult,
+) -> Result<BatchItemStatus, ProgramError> {
+    match prepared {
+        Ok(prepared) => {
+            apply(prepared)?;
+            Ok(BatchItemStatus::Done)
+        }
+        Err(err) => {
+            msg!("item {} skipped: {:?}", index, err);
+            Ok(BatchItemStatus::Skipped(err.into()))
+        }
+    }
+}
+
+pub fn write_batch_result(statuses: &[BatchItemStatus]) -> Result<(), ProgramError> {
+    let data = statuses.try_to_vec().map_err(|_| ProgramError::InvalidArgument)?;
+    set_return_data(&data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::pubkey::Pubkey;
+
+    #[test]
+    fn failed_validation_skips_only_its_item() {
+        let mut applied = Vec::new();
+        let statuses = [Ok(1), Err(ProgramError::InvalidArgument), Ok(3)]
+            .into_iter()
+            .enumerate()
+            .map(|(index, prepared)| run_batch_item(index, prepared, |item| {
+                applied.push(item);
+                Ok(())
+            }))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(applied, vec![1, 3]);
+        assert_eq!(statuses, vec![
+            BatchItemStatus::Done,
+            BatchItemStatus::Skipped(ProgramError::InvalidArgument.into()),
+            BatchItemStatus::Done,
+        ]);
+
+        // an item failing after it started writing can't be skipped
+        assert!(run_batch_item(0, Ok(()), |_| Err(ProgramError::InsufficientFunds)).is_err());
+    }
+
+    #[test]
+    fn shared_accounts_fill_their_slots() {
+        let keys: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+        let mut lamports = [0u64; 6];
+        let mut data: [Vec<u8>; 6] = Default::default();
+        let infos: Vec<AccountInfo> = keys.iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|((key, lamports), data)| AccountInfo::new(key, false, false, lamports, data, key, false, 0))
+            .collect();
+        let (shared, group) = infos.split_at(2);
+
+        // shared at 0 and 2, the item's own at 1 and 3, then its trailing accounts
+        let laid_out = expand_item_accounts(shared, &[0, 2], group);
+        let order: Vec<Pubkey> = laid_out.iter().map(|info| *info.key).collect();
+        assert_eq!(order, vec![keys[0], keys[2], keys[1], keys[3], keys[4], keys[5]]);
+
+        assert_eq!(expand_item_accounts(shared, &[0, 2], &group[..0]).len(), 1);
+    }
+}
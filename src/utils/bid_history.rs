@@ -0,0 +1,93 @@
+//! Keep each auction's bid trail
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, pubkey::Pubkey
+};
+use web3_utils::check::{check_account_key, check_account_owner};
+
+use crate::{
+    state::bid_history::{BidEntry, BidHistory, BidHistoryHeader, MAX_BID_HISTORY, get_bid_history_key},
+    utils::create_pda_account,
+};
+
+/// Open an auction's history with its opening bid. The opener covers the rent of a full history
+/// up front, so bidders never pay for it to grow.
+pub fn open_bid_history<'a>(
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    history: &AccountInfo<'a>,
+    name_state: &Pubkey,
+    entry: BidEntry,
+) -> ProgramResult {
+
+    let (history_key, bump) = get_bid_history_key(name_state);
+    check_account_key(history, &history_key)?;
+
+    let mut history_data = BidHistory::new(*name_state);
+    history_data.push(entry)?;
+
+    create_pda_account(
+        payer,
+        history,
+        system_program,
+        BidHistoryHeader::space(MAX_BID_HISTORY),
+        &[b"bid_history", name_state.as_ref(), &[bump]],
+    )?;
+    history_data.pack(&mut history.data.borrow_mut());
+
+    Ok(())
+}
+
+/// Append a bid to the history, which was opened at its full size.
+/// Auctions opened before histories existed have none and are left alone.
+pub fn record_bid(
+    history: &AccountInfo,
+    name_state: &Pubkey,
+    entry: BidEntry,
+) -> ProgramResult {
+
+    check_account_key(history, &get_bid_history_key(name_state).0)?;
+    if history.data_is_empty() {
+        msg!("auction has no bid history");
+        return Ok(());
+    }
+    check_account_owner(history, &crate::ID)?;
+
+    let mut history_data = BidHistory::unpack(&history.data.borrow())?;
+    history_data.push(entry)?;
+    if history.data_len() < BidHistoryHeader::space(history_data.entries.len()) {
+        msg!("bid history too short, bid not recorded");
+        return Ok(());
+    }
+    history_data.pack(&mut history.data.borrow_mut());
+
+    Ok(())
+}
+
+/// Close a settled auction's history, its rent goes to `receiver`
+pub fn close_bid_history(
+    history: &AccountInfo,
+    name_state: &Pubkey,
+    receiver: &AccountInfo,
+) -> ProgramResult {
+
+    check_account_key(history, &get_bid_history_key(name_state).0)?;
+    if history.data_is_empty() {
+        msg!("auction has no bid history");
+        return Ok(());
+    }
+    check_account_owner(history, &crate::ID)?;
+
+    let lamports = **history.lamports.borrow();
+    **history.try_borrow_mut_lamports()? -= lamports;
+    **receiver.try_borrow_mut_lamports()? += lamports;
+    msg!("return bid history rent: {} to {}", lamports, receiver.key);
+
+    let mut data = history.try_borrow_mut_data()?;
+    for byte in data.iter_mut() {
+        *byte = 0;
+    }
+    history.assign(&solana_program::system_program::ID);
+
+    Ok(())
+}
@@ -0,0 +1,21 @@
+//! Events logged with `sol_log_data`, indexers decode them with borsh
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{entrypoint::ProgramResult, log::sol_log_data, program_error::ProgramError, pubkey::Pubkey};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq)]
+pub enum Event {
+    /// A usr moved to another referrer
+    ReferrerChanged {
+        usr: Pubkey,
+        old_referrer: Pubkey,
+        new_referrer: Pubkey,
+        time: i64,
+    },
+}
+
+pub fn emit(event: &Event) -> ProgramResult {
+    let data = event.try_to_vec().map_err(|_| ProgramError::InvalidArgument)?;
+    sol_log_data(&[&data]);
+    Ok(())
+}
@@ -0,0 +1,38 @@
+//! Keep the leaderboards the admins have created up to date
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, pubkey::Pubkey
+};
+use web3_utils::check::{check_account_key, check_account_owner};
+
+use crate::{
+    state::leaderboard::{Leaderboard, LeaderboardEntry, LeaderboardKind, get_leaderboard_key},
+    utils::get_now_time,
+};
+
+/// Rank a key at its new value -- nothing is ranked until the admins size the leaderboard
+pub fn submit_to_leaderboard(
+    leaderboard: &AccountInfo,
+    kind: LeaderboardKind,
+    key: Pubkey,
+    value: u64,
+) -> ProgramResult {
+
+    check_account_key(leaderboard, &get_leaderboard_key(kind).0)?;
+    if leaderboard.data_is_empty() {
+        return Ok(());
+    }
+    check_account_owner(leaderboard, &crate::ID)?;
+
+    let mut data_ref = leaderboard.try_borrow_mut_data()?;
+    let mut board = Leaderboard::unpack(&data_ref)?;
+    if board.header.kind != kind {
+        msg!("leaderboard ranks {:?}, not {:?}", board.header.kind, kind);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    board.submit(LeaderboardEntry { key, value, time: get_now_time()? });
+    board.pack(&mut data_ref);
+
+    Ok(())
+}
@@ -0,0 +1,90 @@
+//! Append to the per-record profit ledgers
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
+};
+use web3_utils::check::{check_account_key, check_account_owner};
+
+use crate::{
+    state::ledger::{LedgerEntry, LedgerHeader, LedgerKind, MAX_LEDGER_ENTRIES, get_ledger_key},
+    utils::{create_pda_account, get_now_time, resize_pda_account},
+};
+
+/// An entry stamped with the current time
+pub fn ledger_entry(
+    kind: LedgerKind,
+    domain: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+) -> Result<LedgerEntry, ProgramError> {
+    Ok(LedgerEntry { kind, domain, mint, amount, time: get_now_time()? })
+}
+
+/// Write an entry to `record`'s ledger, created on first use.
+/// The payer covers the rent of each new slot until the ledger is full, then the oldest entry goes.
+pub fn append_ledger<'a>(
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    record: &AccountInfo<'a>,
+    ledger: &AccountInfo<'a>,
+    entry: &LedgerEntry,
+) -> ProgramResult {
+
+    let (ledger_key, bump) = get_ledger_key(record.key);
+    check_account_key(ledger, &ledger_key)?;
+
+    let mut header = if ledger.data_is_empty() {
+        create_pda_account(
+            payer,
+            ledger,
+            system_program,
+            LedgerHeader::space(0),
+            &[b"ledger", record.key.as_ref(), &[bump]],
+        )?;
+        LedgerHeader { record: *record.key, count: 0 }
+    } else {
+        LedgerHeader::unpack_from_slice(&ledger.data.borrow())?
+    };
+
+    if header.len() < MAX_LEDGER_ENTRIES {
+        resize_pda_account(payer, ledger, system_program, LedgerHeader::space(header.len() + 1))?;
+    }
+
+    let mut data = ledger.try_borrow_mut_data()?;
+    let offset = LedgerHeader::space(header.next_slot());
+    entry.pack_into_slice(&mut data[offset..offset + LedgerEntry::LEN]);
+
+    header.count = header.count.saturating_add(1);
+    header.pack_into_slice(&mut data);
+    msg!("ledger entry {}: {:?} {}", header.count, entry.kind, entry.amount);
+
+    Ok(())
+}
+
+/// Close a closed record's ledger, its rent goes to `receiver`
+pub fn close_ledger(
+    record: &AccountInfo,
+    ledger: &AccountInfo,
+    receiver: &AccountInfo,
+) -> ProgramResult {
+
+    check_account_key(ledger, &get_ledger_key(record.key).0)?;
+    if ledger.data_is_empty() {
+        msg!("record has no ledger");
+        return Ok(());
+    }
+    check_account_owner(ledger, &crate::ID)?;
+
+    let lamports = **ledger.lamports.borrow();
+    **ledger.try_borrow_mut_lamports()? -= lamports;
+    **receiver.try_borrow_mut_lamports()? += lamports;
+    msg!("return ledger rent: {} to {}", lamports, receiver.key);
+
+    let mut data = ledger.try_borrow_mut_data()?;
+    for byte in data.iter_mut() {
+        *byte = 0;
+    }
+    ledger.assign(&solana_program::system_program::ID);
+
+    Ok(())
+}
@@ -3,8 +3,36 @@ pub mod price;
 pub mod time;
 pub mod promotion_inspect;
 pub mod transfer_by_chain;
+pub mod batch;
+pub mod token;
+pub mod event;
+pub mod ledger;
+pub mod vault;
+pub mod leaderboard;
+pub mod stats;
+pub mod auction_index;
+pub mod portfolio;
+pub mod wrapped;
+pub mod bid_history;
 
 pub use pda::*;
 pub use price::*;
 pub use time::*;
 
+
+use solana_program::{account_info::AccountInfo, msg, program_error::ProgramError};
+
+/// Take the next `len` accounts as a slice, so accounts can still follow it
+pub fn next_account_slice<'a, 'b>(
+    accounts_iter: &mut std::slice::Iter<'a, AccountInfo<'b>>,
+    len: u8,
+) -> Result<&'a [AccountInfo<'b>], ProgramError> {
+    let rest = accounts_iter.as_slice();
+    if rest.len() < len as usize {
+        msg!("expected {} more accounts, got {}", len, rest.len());
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let (slice, rest) = rest.split_at(len as usize);
+    *accounts_iter = rest.iter();
+    Ok(slice)
+}
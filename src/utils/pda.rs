@@ -1,4 +1,7 @@
-use solana_program::{hash::hashv};
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, hash::hashv, program::{invoke, invoke_signed}, rent::Rent, sysvar::Sysvar
+};
+use solana_system_interface::instruction as system_instruction;
 use web3_domain_name_service::utils::HASH_PREFIX;
 
 pub fn get_hashed_name(name: &str) -> Vec<u8> {
@@ -7,6 +10,77 @@ pub fn get_hashed_name(name: &str) -> Vec<u8> {
         .to_vec()
 }
 
+/// Fund, allocate and assign a registrar PDA.
+/// Works on addresses that already hold lamports, where `create_account` would fail.
+pub fn create_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    space: usize,
+    signer_seeds: &[&[u8]],
+) -> ProgramResult {
+    let rent = Rent::get()?;
+    let lamports = rent.minimum_balance(space).saturating_sub(account.lamports());
+
+    if lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, lamports),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+
+    invoke_signed(
+        &system_instruction::allocate(account.key, space as u64),
+        &[account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+
+    invoke_signed(
+        &system_instruction::assign(account.key, &crate::ID),
+        &[account.clone(), system_program.clone()],
+        &[signer_seeds],
+    )
+}
+
+/// Grow a registrar account written by an older layout, the payer covers the extra rent
+pub fn resize_pda_account<'a>(
+    payer: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    new_len: usize,
+) -> ProgramResult {
+    if account.data_len() >= new_len {
+        return Ok(());
+    }
+
+    let rent = Rent::get()?;
+    let lamports = rent.minimum_balance(new_len).saturating_sub(account.lamports());
+    if lamports > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, account.key, lamports),
+            &[payer.clone(), account.clone(), system_program.clone()],
+        )?;
+    }
+
+    account.resize(new_len)
+}
+
+/// Shrink a registrar account, the rent it no longer needs goes to `receiver`
+pub fn shrink_pda_account(
+    account: &AccountInfo,
+    receiver: &AccountInfo,
+    new_len: usize,
+) -> ProgramResult {
+    if account.data_len() <= new_len {
+        return Ok(());
+    }
+    account.resize(new_len)?;
+
+    let excess = account.lamports().saturating_sub(Rent::get()?.minimum_balance(new_len));
+    **account.try_borrow_mut_lamports()? -= excess;
+    **receiver.try_borrow_mut_lamports()? += excess;
+    Ok(())
+}
 
 /// Check if root name conflicts with reserved domain names
 pub fn is_reserved_root(root_name: &str) -> bool {
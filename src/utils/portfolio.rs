@@ -0,0 +1,68 @@
+//! Keep wallets' portfolios in step with their bids and wins
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, pubkey::Pubkey
+};
+use web3_utils::check::{check_account_key, check_account_owner};
+
+use crate::{
+    state::portfolio::{PortfolioRecord, get_portfolio_key},
+    utils::{create_pda_account, resize_pda_account},
+};
+
+/// Update a wallet's portfolio, creating it or growing it at the payer's expense
+pub fn update_portfolio<'a>(
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    portfolio: &AccountInfo<'a>,
+    owner: &Pubkey,
+    update: impl FnOnce(&mut PortfolioRecord),
+) -> ProgramResult {
+
+    let (portfolio_key, bump) = get_portfolio_key(owner);
+    check_account_key(portfolio, &portfolio_key)?;
+
+    let mut record = if portfolio.data_is_empty() {
+        PortfolioRecord::new(*owner)
+    } else {
+        check_account_owner(portfolio, &crate::ID)?;
+        PortfolioRecord::unpack(&portfolio.data.borrow())?
+    };
+    update(&mut record);
+
+    let space = record.space();
+    if portfolio.data_is_empty() {
+        create_pda_account(
+            payer,
+            portfolio,
+            system_program,
+            space,
+            &[b"portfolio", owner.as_ref(), &[bump]],
+        )?;
+    } else {
+        resize_pda_account(payer, portfolio, system_program, space)?;
+    }
+    record.pack(&mut portfolio.data.borrow_mut());
+
+    Ok(())
+}
+
+/// Take something off a wallet's portfolio -- a wallet without one has nothing to take
+pub fn prune_portfolio(
+    portfolio: &AccountInfo,
+    owner: &Pubkey,
+    update: impl FnOnce(&mut PortfolioRecord),
+) -> ProgramResult {
+
+    check_account_key(portfolio, &get_portfolio_key(owner).0)?;
+    if portfolio.data_is_empty() {
+        return Ok(());
+    }
+    check_account_owner(portfolio, &crate::ID)?;
+
+    let mut record = PortfolioRecord::unpack(&portfolio.data.borrow())?;
+    update(&mut record);
+    record.pack(&mut portfolio.data.borrow_mut());
+
+    Ok(())
+}
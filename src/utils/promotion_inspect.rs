@@ -3,177 +3,130 @@ use solana_program::account_info::AccountInfo;
 use solana_program::msg;
 use solana_program::program_error::ProgramError;
 use solana_program::program_pack::Pack;
+use solana_program::pubkey::Pubkey;
 
 
-use crate::processor::finalize_name::Accounts;
-use crate::state::{ReferrerRecordHeader};
-use crate::utils::share_with_cap;
-
-// pub fn settle_qualifications_verify(
-//     accounts: &Accounts<'_, AccountInfo<'_>>,
-//     highest_bidder: &Pubkey
-// ) -> ProgramResult {
-
-//     if accounts.fee_payer.key == highest_bidder {
-//         msg!("is the bidder");
-//         return Ok(())
-//     }
-//     if accounts.referrer_a.key == accounts.fee_payer.key {
-//         msg!("is the referrer a");
-//         return Ok(())
-//     }
-//     if let Some(refferr_b) = accounts.referrer_b {
-//         if refferr_b.key == accounts.fee_payer.key {
-//             msg!("is the referrer b");
-//             return Ok(())
-//         }
-//     }
-//     if let Some(refferr_c) = accounts.referrer_c {
-//         if refferr_c.key == accounts.fee_payer.key {
-//             msg!("is the referrer c");
-//             return Ok(())
-//         }
-//     }
-
-//     msg!("you have not qualification ro confirm the transaction");
-//     Err(ProgramError::InvalidArgument)
-// }
-
-fn referrer_profit_add(
-    referrer_record: Option<&AccountInfo>,
+use crate::state::{ReferrerRecordHeader, config::Tier, ledger::LedgerKind};
+use crate::utils::ledger::{append_ledger, ledger_entry};
+use crate::utils::resize_pda_account;
+use crate::utils::transfer_by_chain::ReferrerChain;
+
+/// Credit a referrer's share and note it in its ledger, returning its performance
+pub fn referrer_profit_add<'b>(
+    accounts: &ReferrerChain<'_, 'b>,
+    referrer_record: &AccountInfo<'b>,
+    ledger: &AccountInfo<'b>,
     // These are the subordinates's shares
     profit_add_sol: u64,
 ) -> Result<u64, ProgramError> {
 
-    if let Some(referrer_record) = referrer_record {
+    // a record written by an older version grows to the current layout
+    resize_pda_account(
+        accounts.payer,
+        referrer_record,
+        accounts.system_program,
+        ReferrerRecordHeader::LEN,
+    )?;
+
+    let performance = {
         let mut data_ref = referrer_record.try_borrow_mut_data()?;
         let mut record_data = 
             ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
 
-        record_data.profit = record_data
-            .profit
-            .checked_add(profit_add_sol)
-            .ok_or(ProgramError::InsufficientFunds)?;
-        msg!("add profit");
-
-        record_data.performance = record_data
-            .performance
-            .checked_add(profit_add_sol)
-            .ok_or(ProgramError::InsufficientFunds)?;
-        msg!("add volumn");
+        if accounts.mint != Pubkey::default() {
+            // token shares are withdrawn in the same token and don't count as performance,
+            // the units aren't comparable with lamports
+            record_data.add_token_profit(&accounts.mint, profit_add_sol)?;
+            msg!("add profit of {}", accounts.mint);
+        } else {
+            record_data.add_profit(profit_add_sol)?;
+            msg!("add profit");
+
+            record_data.performance = record_data
+                .performance
+                .checked_add(profit_add_sol)
+                .ok_or(ProgramError::InsufficientFunds)?;
+            msg!("add volumn");
+        }
 
-        record_data.pack_into_slice(&mut data_ref);
+        record_data.try_pack_into_slice(&mut data_ref)?;
+        record_data.performance
+    };
 
-        return Ok(record_data.performance);
-    }
+    append_ledger(
+        accounts.payer,
+        accounts.system_program,
+        referrer_record,
+        ledger,
+        &ledger_entry(LedgerKind::ReferralShare, accounts.domain, accounts.mint, profit_add_sol)?,
+    )?;
 
-    msg!("should exist");
-    return Err(ProgramError::InvalidArgument);
-    
+    Ok(performance)
 }
 
-fn up_level_to(
-    record_will_up: Option<&AccountInfo>,
-    record_the_levfel: Option<&AccountInfo>
-) -> ProgramResult {
-
-    if let (Some(record_up), Some(record_level)) = (record_will_up, record_the_levfel){
-        let new_referrer = 
-            ReferrerRecordHeader::unpack_from_slice(&record_level.try_borrow_data()?)?.referrer_account;
-
-        let mut data_ref = record_up.try_borrow_mut_data()?;
-        let mut record_up_data = 
-            ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
-        
-        record_up_data.referrer_account = new_referrer;
-        record_up_data.pack_into_slice(&mut data_ref);
-
-        msg!("update to {:?}", new_referrer);
+/// The highest tier whose threshold the performance reaches, 0 below the first one
+pub fn tier_for(tiers: &[Tier], performance: u64) -> u8 {
+    tiers
+        .iter()
+        .take_while(|tier| performance >= tier.threshold)
+        .count() as u8
+}
 
-        Ok(())
-    }else {
-        msg!("shoul provide the two accounts");
-        return Err(ProgramError::InvalidArgument);  
-    } 
+/// The extra share a record of `tier` earns, 0 for the base tier or one no longer configured
+pub fn tier_rate(tiers: &[Tier], tier: u8) -> u64 {
+    match (tier as usize).checked_sub(1).and_then(|index| tiers.get(index)) {
+        Some(tier) => tier.rate,
+        None => 0,
+    }
 }
 
-/// Check if referrer A and referrer B need to upgrade, and then perform the upgrade operation.
+/// Raise the tier of every credited record whose performance reached a higher threshold,
+/// the referral lineage is left as it is
 pub fn promotion_inspect(
-    // which referrer is vault
-    // 0 -- no vault 1 -- A 2 -- B 3 -- C 
-    who_vault: u8,
-    accounts: &Accounts<'_, AccountInfo<'_>>,
-    referrer_lamports: u64,
+    accounts: &ReferrerChain<'_, '_>,
+    // the credited records, nearest first, with their performance after the credit
+    credited: &[(&AccountInfo, u64)],
 ) -> ProgramResult {
 
-    match who_vault {
-        0 => {
-            // chain new_owner -> A -> B -> C -> ...
-            msg!("all not vault");
-            let a_performance = referrer_profit_add(
-                accounts.referrer_a_record,
-                share_with_cap(referrer_lamports, 520_000_000)?,
-            )?;
-
-            let b_performance = referrer_profit_add(
-                accounts.referrer_b_record,
-                share_with_cap(referrer_lamports, 260_000_000)?,
-            )?;
-            
-            let c_performance = referrer_profit_add(
-                accounts.referrer_c_record,
-                share_with_cap(referrer_lamports, 130_000_000)?
-            )?;
-
-            // check b frist 
-            if b_performance > c_performance {
-                up_level_to(
-                    accounts.referrer_b_record, 
-                    accounts.referrer_c_record
-                )?;
-                msg!("up b to c's level")
-            }
-
-            if a_performance > b_performance {
-                up_level_to(
-                    accounts.referrer_a_record, 
-                    accounts.referrer_b_record
-                )?;
-            }
-        }
-        1 => {
-            msg!("referrer A is vault");
-        }
-        2 => {
-            msg!("referrer B is vault, means A is highest level");
-            referrer_profit_add(
-                accounts.referrer_a_record,
-                share_with_cap(referrer_lamports, 520_000_000)?
-            )?;
-        }
-        3 => {
-            msg!("referrer C is vault, only check wheather A is going to up level");
-            let a_performance = referrer_profit_add(
-                accounts.referrer_a_record,
-                share_with_cap(referrer_lamports, 520_000_000)?,
-            )?;
-
-            let b_performance = referrer_profit_add(
-                accounts.referrer_b_record,
-                share_with_cap(referrer_lamports, 260_000_000)?,
-            )?;
-
-            if a_performance > b_performance {
-                up_level_to(
-                    accounts.referrer_a_record, 
-                    accounts.referrer_b_record
-                )?;
-            }
-        }
-        _ => {
-            return Err(ProgramError::InvalidArgument);
+    if accounts.mint != Pubkey::default() {
+        msg!("token shares don't change performance");
+        return Ok(());
+    }
+
+    for (level, (record, performance)) in credited.iter().enumerate() {
+        let tier = tier_for(&accounts.tiers, *performance);
+
+        let mut data_ref = record.try_borrow_mut_data()?;
+        let mut record_data = ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
+        if tier > record_data.tier {
+            record_data.tier = tier;
+            record_data.try_pack_into_slice(&mut data_ref)?;
+            msg!("level {} up to tier {}", level, tier);
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tiers_follow_thresholds() {
+        let tiers = [
+            Tier { threshold: 100, rate: 10 },
+            Tier { threshold: 1_000, rate: 20 },
+        ];
+
+        assert_eq!(tier_for(&tiers, 99), 0);
+        assert_eq!(tier_for(&tiers, 100), 1);
+        assert_eq!(tier_for(&tiers, 5_000), 2);
+        assert_eq!(tier_for(&[], 5_000), 0);
+
+        assert_eq!(tier_rate(&tiers, 0), 0);
+        assert_eq!(tier_rate(&tiers, 2), 20);
+        // a tier the admins have since dropped earns nothing extra
+        assert_eq!(tier_rate(&tiers[..1], 2), 0);
+    }
+}
@@ -0,0 +1,54 @@
+//! Count protocol activity into the statistics account
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, program_pack::Pack
+};
+use web3_utils::check::{check_account_key, check_account_owner};
+
+use crate::{
+    state::stats::{StatsRecord, get_stats_key},
+    utils::{get_now_time, math},
+};
+
+/// Update the statistics with the current time -- nothing is counted until the admins create them
+pub fn record_stats(
+    stats: &AccountInfo,
+    update: impl FnOnce(&mut StatsRecord, i64) -> ProgramResult,
+) -> ProgramResult {
+
+    check_account_key(stats, &get_stats_key().0)?;
+    if stats.data_is_empty() {
+        return Ok(());
+    }
+    check_account_owner(stats, &crate::ID)?;
+
+    let mut data_ref = stats.try_borrow_mut_data()?;
+    let mut record = StatsRecord::unpack_from_slice(&data_ref)?;
+    update(&mut record, get_now_time()?)?;
+    record.pack_into_slice(&mut data_ref);
+
+    Ok(())
+}
+
+/// Count a renewal or subdomain fee paid into the vault
+pub fn record_fee(stats: &AccountInfo, fee: u64) -> ProgramResult {
+    record_stats(stats, |stats, now| {
+        stats.fees = math::add(stats.fees, fee)?;
+        let today = stats.today(now);
+        today.fees = math::add(today.fees, fee)?;
+        Ok(())
+    })
+}
+
+/// Count a withdrawal of lamport profit, `fee` of which stayed with the vault
+pub fn record_withdrawal(stats: &AccountInfo, amount: u64, fee: u64) -> ProgramResult {
+    record_stats(stats, |stats, now| {
+        stats.withdrawals = math::add(stats.withdrawals, 1)?;
+        stats.withdrawn = math::add(stats.withdrawn, amount)?;
+        stats.fees = math::add(stats.fees, fee)?;
+        let today = stats.today(now);
+        today.withdrawn = math::add(today.withdrawn, amount)?;
+        today.fees = math::add(today.fees, fee)?;
+        Ok(())
+    })
+}
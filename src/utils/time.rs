@@ -9,31 +9,45 @@ pub const TIME_LIMIT: i64 = 2592000; // 30 days in seconds
 #[cfg(feature = "devnet")]
 pub const TIME_LIMIT: i64 = 300; // 2 minutes in seconds
 
+#[cfg(not(feature = "devnet"))]
+pub const REGISTRATION_PERIOD: i64 = 31536000; // 365 days in seconds
+#[cfg(feature = "devnet")]
+pub const REGISTRATION_PERIOD: i64 = 3600; // 1 hour in seconds
+
+#[cfg(not(feature = "devnet"))]
+pub const DEFAULT_REFERRER_MATURITY: i64 = 86400; // 1 day in seconds
+#[cfg(feature = "devnet")]
+pub const DEFAULT_REFERRER_MATURITY: i64 = 60; // 1 minute in seconds
+
+/// Longest term a single renewal can buy
+pub const MAX_RENEW_YEARS: u8 = 10;
+
 pub fn get_now_time() -> Result<i64, ProgramError> {
     let clock = Clock::get()?;
     Ok(clock.unix_timestamp)
 }
 
-/// Check if the given timestamp has exceeded TIME_LIMIT
-/// Returns true if current time >= timestamp + TIME_LIMIT
-pub fn can_settle(timestamp: i64) -> Result<bool, ProgramError> {
+/// Check if an auction ending at `end_time` can be settled
+/// Returns true if current time >= end_time
+pub fn can_settle(end_time: i64) -> Result<bool, ProgramError> {
     let now = get_now_time()?;
-    let expiration_time = timestamp.checked_add(TIME_LIMIT)
-        .ok_or_else(|| {
-            msg!("Timestamp overflow in time limit check");
-            ProgramError::InvalidArgument
-        })?;
-    Ok(now >= expiration_time)
+    Ok(now >= end_time)
 }
 
-/// if the referrer was created within the last three days, it's not allowed to be a referrer
+/// A record younger than `maturity` seconds can't be used as a referrer yet
 pub fn if_referrer_valid(
-    referrer_state: ReferrerRecordHeader
+    referrer_state: &ReferrerRecordHeader,
+    maturity: i64,
 ) -> Result<bool, ProgramError> {
     let now = get_now_time()?;
+    let mature_time = referrer_state.create_time.checked_add(maturity)
+        .ok_or_else(|| {
+            msg!("Timestamp overflow in referrer maturity check");
+            ProgramError::InvalidArgument
+        })?;
 
-    if now <= referrer_state.create_time + 1 {
-        msg!("this account needs to wait for one day");
+    if now < mature_time {
+        msg!("this account can refer others from {}", mature_time);
         return Ok(false);
     }
 
@@ -0,0 +1,126 @@
+//! SPL token payments -- the vault keeps tokens in its associated token accounts
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::{invoke, invoke_signed}, program_error::ProgramError, pubkey::Pubkey
+};
+use spl_associated_token_account_client::{
+    address::get_associated_token_address, instruction::create_associated_token_account_idempotent
+};
+use web3_utils::check::{check_account_key, check_account_owner};
+
+use crate::constants::return_vault_key;
+
+
+/// The token accounts a bid paid in SPL tokens moves funds through
+pub struct TokenPayment<'a, 'b> {
+    pub token_program: &'a AccountInfo<'b>,
+    pub mint: &'a AccountInfo<'b>,
+    /// the bidder's token account, the bidder signs the transfer
+    pub payer_token_account: &'a AccountInfo<'b>,
+    /// the vault's associated token account for `mint`
+    pub vault_token_account: &'a AccountInfo<'b>,
+    /// where the outbid bid goes back to -- only when raising a bid
+    pub refund: Option<TokenRefund<'a, 'b>>,
+}
+
+/// The accounts an outbid token bid is paid back through
+pub struct TokenRefund<'a, 'b> {
+    /// the outbid bidder's associated token account for the mint -- recreated if it was closed
+    pub token_account: &'a AccountInfo<'b>,
+    /// The associated token account program
+    pub associated_token_program: &'a AccountInfo<'b>,
+}
+
+impl TokenPayment<'_, '_> {
+    pub fn check(&self) -> ProgramResult {
+        check_account_key(self.token_program, &spl_token::ID)?;
+        msg!("token program id ok");
+        check_account_owner(self.mint, &spl_token::ID)?;
+        check_account_key(self.vault_token_account, &get_vault_token_key(self.mint.key))?;
+        msg!("vault token account ok");
+
+        Ok(())
+    }
+}
+
+/// The vault's associated token account for a mint
+pub fn get_vault_token_key(mint: &Pubkey) -> Pubkey {
+    get_associated_token_address(&return_vault_key().0, mint)
+}
+
+/// Move tokens out of an account whose owner signed the transaction
+pub fn transfer_token<'a>(
+    token_program: &AccountInfo<'a>,
+    source: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    authority: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    invoke(
+        &spl_token::instruction::transfer(
+            token_program.key, source.key, destination.key, authority.key, &[], amount
+        )?,
+        &[source.clone(), destination.clone(), authority.clone(), token_program.clone()],
+    )
+}
+
+/// Pay an outbid bid back in full, straight into the bidder's associated token account.
+/// The account is recreated at the payer's expense if it was closed, so the refund can't be refused
+/// and an outbid bidder can't hold the auction up.
+pub fn refund_token_bid<'a>(
+    payment: &TokenPayment<'_, 'a>,
+    payer: &AccountInfo<'a>,
+    bidder: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let refund = payment.refund.as_ref().ok_or_else(|| {
+        msg!("should provide the last bidder's token account");
+        ProgramError::NotEnoughAccountKeys
+    })?;
+    check_account_key(refund.associated_token_program, &spl_associated_token_account_client::program::ID)?;
+    check_account_key(refund.token_account, &get_associated_token_address(bidder.key, payment.mint.key))?;
+
+    invoke(
+        &create_associated_token_account_idempotent(
+            payer.key,
+            bidder.key,
+            payment.mint.key,
+            payment.token_program.key,
+        ),
+        &[
+            payer.clone(),
+            refund.token_account.clone(),
+            bidder.clone(),
+            payment.mint.clone(),
+            system_program.clone(),
+            payment.token_program.clone(),
+            refund.associated_token_program.clone(),
+        ],
+    )?;
+
+    transfer_token(payment.token_program, payment.payer_token_account, refund.token_account, payer, amount)
+}
+
+/// Move tokens out of the vault's token account, signed by the vault PDA
+pub fn transfer_token_from_vault<'a>(
+    token_program: &AccountInfo<'a>,
+    vault_token_account: &AccountInfo<'a>,
+    destination: &AccountInfo<'a>,
+    vault: &AccountInfo<'a>,
+    amount: u64,
+) -> ProgramResult {
+    let (vault_key, bump) = return_vault_key();
+    if vault.key != &vault_key {
+        msg!("vault error");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            token_program.key, vault_token_account.key, destination.key, &vault_key, &[], amount
+        )?,
+        &[vault_token_account.clone(), destination.clone(), vault.clone(), token_program.clone()],
+        &[&[b"vault", &[bump]]],
+    )
+}
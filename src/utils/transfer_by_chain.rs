@@ -1,114 +1,193 @@
 
 use solana_program::{
-    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack, pubkey::Pubkey
 };
 
 use web3_utils::check::check_account_key;
 
-use crate::{state::{ReferrerRecordHeader, get_referrer_record_key}, utils::{promotion_inspect::promotion_inspect}};
+use crate::{state::{ReferrerRecordHeader, config::Tier, get_referrer_record_key, leaderboard::LeaderboardKind, ledger::{LedgerEntry, get_ledger_key}}, utils::{leaderboard::submit_to_leaderboard, ledger::append_ledger, promotion_inspect::{promotion_inspect, referrer_profit_add, tier_rate}, resize_pda_account, share_with_cap}};
+
+
+/// The paying usr and the records of its referrers, nearest first
+pub struct ReferrerChain<'a, 'b> {
+    /// the currency the shares are credited in -- default means lamports
+    pub mint: Pubkey,
+    /// pays to grow legacy records that get credited in tokens
+    pub payer: &'a AccountInfo<'b>,
+    pub system_program: &'a AccountInfo<'b>,
+    pub vault: &'a AccountInfo<'b>,
+    /// the referrer leaderboard, credited referrers are ranked by their new performance
+    pub referrer_leaderboard: &'a AccountInfo<'b>,
+    /// the usr who paid -- the chain starts from its record
+    pub usr: &'a AccountInfo<'b>,
+    pub usr_record: &'a AccountInfo<'b>,
+    /// the domain the payment is for, noted in the ledgers
+    pub domain: Pubkey,
+    /// a record and its ledger per level until the vault or the share table runs out
+    pub referrer_records: &'a [AccountInfo<'b>],
+    /// share of each level, nearest first (1e9 = 100%)
+    pub shares: Vec<u64>,
+    /// performance tiers, from the lowest up
+    pub tiers: Vec<Tier>,
+}
 
+impl ReferrerChain<'_, '_> {
+    /// Walk the chain like `transfer_by_referrer_chain` without writing anything,
+    /// so a batch can skip a settlement whose chain is off before any payout
+    pub fn validate(&self) -> ProgramResult {
 
-// 11.10 changed: cancle all directly transfer SOL
+        let (usr_record_key, _) = get_referrer_record_key(self.usr.key);
+        check_account_key(self.usr_record, &usr_record_key)?;
 
-pub fn transfer_by_referrer_chain(
-    accounts: &crate::processor::finalize_name::Accounts<'_, AccountInfo<'_>>,
-    referrer_lamports: u64,
-) -> ProgramResult {
+        let mut referrer = usr_referrer(self.usr_record, self.vault.key)?;
+        let mut records = self.referrer_records.chunks(2);
 
-    let vault = accounts.vault;
-    
-
-    let domain_owner = accounts.new_domain_owner;
-    let (owner_record, _) = get_referrer_record_key(domain_owner.key);
-    check_account_key(accounts.referrer_record, &owner_record)?;
-
-    let referrer_usr_data = 
-        ReferrerRecordHeader::unpack_from_slice(&accounts.referrer_record.data.borrow())?;
+        for level in 0..self.shares.len() {
+            if &referrer == self.vault.key {
+                break;
+            }
 
-    let mut who_vault: u8 = 0;
+            let (record, ledger) = match records.next() {
+                Some([record, ledger]) => (record, ledger),
+                _ => {
+                    msg!("should provide the record and ledger of level {} referrer {}", level, referrer);
+                    return Err(ProgramError::InvalidArgument);
+                }
+            };
+            check_account_key(record, &get_referrer_record_key(&referrer).0)?;
+            if record.data_is_empty() {
+                break;
+            }
+            check_account_key(ledger, &get_ledger_key(record.key).0)?;
 
-    if &referrer_usr_data.referrer_account != vault.key{
+            referrer = ReferrerRecordHeader::unpack_from_slice(&record.data.borrow())?.referrer_account;
+        }
 
-        if &referrer_usr_data.referrer_account != accounts.referrer_a.key {
-            msg!("provide fault referrer A");
+        if records.next().is_some() {
+            msg!("more referrer records than the chain");
             return Err(ProgramError::InvalidArgument);
         }
 
-        if let Some(referrer_a_record) = accounts.referrer_a_record {
+        Ok(())
+    }
+}
 
-            let (verify_record_a, _) = get_referrer_record_key(&accounts.referrer_a.key);
-            check_account_key(referrer_a_record, &verify_record_a)?;
+// 11.10 changed: cancle all directly transfer SOL
 
-            let a_record_data = 
-                ReferrerRecordHeader::unpack_from_slice(&referrer_a_record.data.borrow())?;
+/// Walk up from the usr's record, crediting each level its share,
+/// until a level's referrer is the vault or the table ends -- returns the total credited
+pub fn transfer_by_referrer_chain(
+    accounts: &ReferrerChain<'_, '_>,
+    referrer_lamports: u64,
+) -> Result<u64, ProgramError> {
 
-            if &a_record_data.referrer_account != vault.key {
-                
-                if let Some(referrer_b) = accounts.referrer_b {
-                    if &a_record_data.referrer_account != referrer_b.key {
-                        msg!("provide fault referrer B");
-                        return Err(ProgramError::InvalidArgument);
-                    }
+    let vault = accounts.vault;
 
-                    if let Some(referrer_b_record) = accounts.referrer_b_record {
+    let (usr_record_key, _) = get_referrer_record_key(accounts.usr.key);
+    check_account_key(accounts.usr_record, &usr_record_key)?;
 
-                        let (verify_record_b, _) = get_referrer_record_key(&referrer_b.key);
-                        check_account_key(referrer_b_record, &verify_record_b)?;
+    let mut referrer = usr_referrer(accounts.usr_record, vault.key)?;
+    let mut records = accounts.referrer_records.chunks(2);
+    let mut credited = Vec::with_capacity(accounts.shares.len());
+    // the record whose referrer the current level is
+    let mut pointing = accounts.usr_record;
+    let mut total = 0u64;
 
-                        let b_record_data = 
-                            ReferrerRecordHeader::unpack_from_slice(&referrer_b_record.data.borrow())?;
+    for (level, share) in accounts.shares.iter().enumerate() {
+        if &referrer == vault.key {
+            msg!("level {} referrer is vault", level);
+            break;
+        }
 
-                        if &b_record_data.referrer_account != vault.key {
+        let (record, ledger) = match records.next() {
+            Some([record, ledger]) => (record, ledger),
+            _ => {
+                msg!("should provide the record and ledger of level {} referrer {}", level, referrer);
+                return Err(ProgramError::InvalidArgument);
+            }
+        };
+        let (record_key, _) = get_referrer_record_key(&referrer);
+        check_account_key(record, &record_key)?;
+
+        if record.data_is_empty() {
+            // the referrer closed its record, its downline falls back to the vault
+            fall_back_to_vault(pointing, vault.key)?;
+            msg!("level {} referrer {} has left, fall back to vault", level, referrer);
+            break;
+        }
 
-                            if let Some(referrer_c) = accounts.referrer_c {
+        let record_data = ReferrerRecordHeader::unpack_from_slice(&record.data.borrow())?;
+        let next_referrer = record_data.referrer_account;
+
+        // a ranked referrer earns its tier's rate on top of the level's share
+        let share = share
+            .checked_add(tier_rate(&accounts.tiers, record_data.tier))
+            .ok_or(ProgramError::InvalidArgument)?;
+        let amount = share_with_cap(referrer_lamports, share)?;
+        let performance = referrer_profit_add(accounts, record, ledger, amount)?;
+        total = total.checked_add(amount).ok_or(ProgramError::InvalidArgument)?;
+        credited.push((record, performance));
+        // token shares leave the performance as it was
+        if accounts.mint == Pubkey::default() {
+            submit_to_leaderboard(accounts.referrer_leaderboard, LeaderboardKind::Referrers, referrer, performance)?;
+        }
 
-                                if &b_record_data.referrer_account != referrer_c.key {
-                                    msg!("provide fault referrer C");
-                                    return Err(ProgramError::InvalidArgument);
-                                }
+        pointing = record;
+        referrer = next_referrer;
+    }
 
-                                if let Some(referrer_c_record)  = accounts.referrer_c_record {
-                                    let (verify_record_c, _) = get_referrer_record_key(&referrer_c.key);
-                                    check_account_key(referrer_c_record, &verify_record_c)?;
-                                }
+    if records.next().is_some() {
+        msg!("more referrer records than the chain");
+        return Err(ProgramError::InvalidArgument);
+    }
 
-                            } else {
-                                msg!("usrB's referrer is't vault, so B should provide a record account");
-                                return Err(ProgramError::InvalidArgument);
-                            }
-                        }else {
-                            msg!("referrer C is vault");
-                            who_vault = 3;
-                        }
-                    } 
+    promotion_inspect(accounts, &credited)?;
 
-                    msg!("vault: {:?}", vault.key);
-                    msg!("vault owner: {:?}", vault.owner);
-                }else {
-                    msg!("usrA's referrer is't vault, so A should provide a record account");
-                    return Err(ProgramError::InvalidArgument);
-                }
+    Ok(total)
+}
 
-            }else {
-                msg!("referrer B is vault");
-                who_vault = 2;
-            }
-        }else {
-            msg!("usr's referrer is't vault, so A should provide a record account");
-            return Err(ProgramError::InvalidArgument);
-        }
-    }else {
-        msg!("referrer A is vault");
-        who_vault = 1;
+/// The usr's referrer -- a usr who closed its record pays nobody up the chain
+fn usr_referrer(usr_record: &AccountInfo, vault: &Pubkey) -> Result<Pubkey, ProgramError> {
+    if usr_record.data_is_empty() {
+        msg!("usr has left, no referral shares");
+        return Ok(*vault);
     }
+    Ok(ReferrerRecordHeader::unpack_from_slice(&usr_record.data.borrow())?.referrer_account)
+}
 
-    promotion_inspect(
-        who_vault, 
-        &accounts, 
-        referrer_lamports,
-    )?;
-
+/// Point a record whose referrer closed its record at the vault
+pub fn fall_back_to_vault(record: &AccountInfo, vault: &Pubkey) -> ProgramResult {
+    let mut data_ref = record.try_borrow_mut_data()?;
+    let mut record_data = ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
+    record_data.referrer_account = *vault;
+    record_data.try_pack_into_slice(&mut data_ref)?;
     Ok(())
 }
 
+/// Credit withdrawable profit that doesn't count as performance, in the entry's currency,
+/// and note it in the record's ledger -- an older record is grown to the current layout
+pub fn add_profit<'a>(
+    record: &AccountInfo<'a>,
+    ledger: &AccountInfo<'a>,
+    entry: &LedgerEntry,
+    payer: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+) -> ProgramResult {
+
+    resize_pda_account(payer, record, system_program, ReferrerRecordHeader::LEN)?;
+
+    {
+        let mut data_ref = record.try_borrow_mut_data()?;
+        let mut record_data = ReferrerRecordHeader::unpack_from_slice(&data_ref)?;
 
+        if entry.mint != Pubkey::default() {
+            record_data.add_token_profit(&entry.mint, entry.amount)?;
+        } else {
+            record_data.add_profit(entry.amount)?;
+        }
+
+        record_data.try_pack_into_slice(&mut data_ref)?;
+    }
+
+    append_ledger(payer, system_program, record, ledger, entry)
+}
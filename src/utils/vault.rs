@@ -0,0 +1,74 @@
+//! Keep the vault's books and its rent
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program_error::ProgramError, program_pack::Pack, rent::Rent, sysvar::Sysvar
+};
+
+use crate::{state::vault::VaultRecord, utils::resize_pda_account};
+
+/// Update the vault record, growing a legacy vault to the current layout first
+pub fn update_vault_record<'a>(
+    payer: &AccountInfo<'a>,
+    vault: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    update: impl FnOnce(&mut VaultRecord) -> ProgramResult,
+) -> ProgramResult {
+
+    resize_pda_account(payer, vault, system_program, VaultRecord::LEN)?;
+
+    let mut data_ref = vault.try_borrow_mut_data()?;
+    let mut vault_record = VaultRecord::unpack_from_slice(&data_ref)?;
+    update(&mut vault_record)?;
+    vault_record.try_pack_into_slice(&mut data_ref)?;
+
+    Ok(())
+}
+
+/// Grow a registrar account to `new_len` with the vault paying the rent out of its free balance,
+/// booked as spent revenue. `payer` only covers what the free balance can't.
+pub fn grow_from_vault<'a>(
+    payer: &AccountInfo<'a>,
+    vault: &AccountInfo<'a>,
+    account: &AccountInfo<'a>,
+    system_program: &AccountInfo<'a>,
+    new_len: usize,
+) -> ProgramResult {
+
+    if account.data_len() >= new_len {
+        return Ok(());
+    }
+
+    let rent = Rent::get()?;
+    let lamports = rent.minimum_balance(new_len).saturating_sub(account.lamports());
+    let mut funded = 0;
+    update_vault_record(payer, vault, system_program, |vault_record| {
+        let free = vault_record.free_balance(vault.lamports(), rent.minimum_balance(VaultRecord::LEN));
+        funded = lamports.min(free);
+        vault_record.revenue -= funded;
+        Ok(())
+    })?;
+    pay_from_vault(vault, account, funded)?;
+    msg!("the vault funds {} of {} lamports to grow {}", funded, lamports, account.key);
+
+    resize_pda_account(payer, account, system_program, new_len)
+}
+
+/// Move lamports out of the vault, never below its rent-exempt minimum
+pub fn pay_from_vault(
+    vault: &AccountInfo,
+    destination: &AccountInfo,
+    amount: u64,
+) -> ProgramResult {
+
+    let rent_exempt = Rent::get()?.minimum_balance(vault.data_len());
+    let left = vault.lamports().checked_sub(amount).ok_or(ProgramError::InsufficientFunds)?;
+    if left < rent_exempt {
+        msg!("vault would drop below its rent-exempt minimum: {}", rent_exempt);
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    **vault.try_borrow_mut_lamports()? -= amount;
+    **destination.try_borrow_mut_lamports()? += amount;
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Find who sells a domain that may be wrapped, and retire its token once it is sold
+
+use solana_program::{
+    account_info::AccountInfo, entrypoint::ProgramResult, msg, program::invoke_signed,
+    program_error::ProgramError, program_option::COption, program_pack::Pack, pubkey::Pubkey
+};
+use spl_token::state::Mint;
+use web3_utils::check::{check_account_key, check_account_owner};
+
+use crate::{central_state, state::wrapped::{WrappedNameRecord, get_wrapped_name_key}};
+
+/// The domain's owner, or the holder of its token while the registrar holds it wrapped.
+/// The wrapped record and token account are only read for a wrapped domain.
+pub fn resolve_seller(
+    name_account: &Pubkey,
+    name_owner: &Pubkey,
+    wrapped_name: &AccountInfo,
+    holder_token_account: &AccountInfo,
+) -> Result<Pubkey, ProgramError> {
+
+    let (wrapped_name_key, _) = get_wrapped_name_key(name_account);
+    if name_owner != &wrapped_name_key {
+        return Ok(*name_owner);
+    }
+
+    check_account_key(wrapped_name, &wrapped_name_key)?;
+    check_account_owner(wrapped_name, &crate::ID)?;
+    check_account_owner(holder_token_account, &spl_token::ID)?;
+
+    let record = WrappedNameRecord::unpack_from_slice(&wrapped_name.data.borrow())?;
+    let holder = record.holder(&holder_token_account.data.borrow())?;
+    msg!("wrapped, token {} held by {}", record.mint, holder);
+
+    Ok(holder)
+}
+
+/// Check the accounts `retire_wrapped_name` takes for a wrapped domain
+pub fn check_retire_accounts(
+    name_account: &Pubkey,
+    wrapped_name: &AccountInfo,
+    mint: &AccountInfo,
+    token_program: &AccountInfo,
+) -> Result<(), ProgramError> {
+
+    check_account_key(wrapped_name, &get_wrapped_name_key(name_account).0)?;
+    check_account_owner(wrapped_name, &crate::ID)?;
+    check_account_key(token_program, &spl_token::ID)?;
+
+    let record = WrappedNameRecord::unpack_from_slice(&wrapped_name.data.borrow())?;
+    check_account_key(mint, &record.mint)?;
+
+    Ok(())
+}
+
+/// Freeze the token of a wrapped domain that was sold and close its record,
+/// so the token no longer passes for the domain. The record's rent goes to `rent_receiver`.
+/// Tokens minted before the central state kept the freeze authority, and a holder's
+/// token account that isn't passed, are left as they are.
+pub fn retire_wrapped_name<'a>(
+    wrapped_name: &AccountInfo<'a>,
+    mint: &AccountInfo<'a>,
+    holder_token_account: Option<&AccountInfo<'a>>,
+    token_program: &AccountInfo<'a>,
+    central_state: &AccountInfo<'a>,
+    rent_receiver: &AccountInfo<'a>,
+) -> ProgramResult {
+
+    let freeze_authority = Mint::unpack(&mint.data.borrow())?.freeze_authority;
+    match holder_token_account {
+        Some(token_account) if freeze_authority == COption::Some(central_state::KEY) => {
+            let central_state_signer_seeds: &[&[u8]] = &[&crate::ID.to_bytes(), &[central_state::NONCE]];
+            invoke_signed(
+                &spl_token::instruction::freeze_account(
+                    token_program.key, token_account.key, mint.key, &central_state::KEY, &[]
+                )?,
+                &[
+                    token_account.clone(),
+                    mint.clone(),
+                    central_state.clone(),
+                    token_program.clone(),
+                ],
+                &[central_state_signer_seeds],
+            )?;
+            msg!("froze {}", token_account.key);
+        }
+        _ => msg!("token {} left unfrozen", mint.key),
+    }
+
+    let lamports = **wrapped_name.lamports.borrow();
+    **wrapped_name.try_borrow_mut_lamports()? -= lamports;
+    **rent_receiver.try_borrow_mut_lamports()? += lamports;
+
+    let mut data = wrapped_name.try_borrow_mut_data()?;
+    for byte in data.iter_mut() {
+        *byte = 0;
+    }
+    wrapped_name.assign(&solana_program::system_program::ID);
+    msg!("wrapped record closed");
+
+    Ok(())
+}